@@ -0,0 +1,67 @@
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use std::io;
+
+const MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// Frames a stream into `\n` (or `\r\n`) terminated lines.
+pub struct LineCodec<S> {
+    stream: S,
+    buf: Vec<u8>,
+}
+
+impl<S> LineCodec<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Reads the next line without its terminator, or `None` at the end of
+    /// the stream.
+    pub async fn read_line(&mut self) -> io::Result<Option<String>> {
+        loop {
+            if let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+                let mut line = self.buf.drain(..=pos).collect::<Vec<u8>>();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                return decode(line).map(Some);
+            }
+            if self.buf.len() > MAX_LINE_LENGTH {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+            }
+
+            let mut chunk = [0u8; 1024];
+            let n = await!(self.stream.read(&mut chunk))?;
+            if n == 0 {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                let line = self.buf.split_off(0);
+                return decode(line).map(Some);
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// Writes `line` followed by `\n`.
+    pub async fn write_line(&mut self, mut line: String) -> io::Result<()> {
+        line.push('\n');
+        await!(self.stream.write_all(line.as_bytes()))?;
+        await!(self.stream.flush())
+    }
+}
+
+fn decode(line: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
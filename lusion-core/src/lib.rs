@@ -1,9 +1,13 @@
 #![feature(async_await, await_macro)]
 
+pub mod codec;
 pub mod handler;
 pub mod net;
+pub mod protocol;
 
 pub mod prelude {
+    pub use super::codec::LineCodec;
     pub use super::handler::Handler;
     pub use super::net::{self, NetServer, NetStream};
+    pub use super::protocol::{LineProtocol, RequestHandler};
 }
@@ -4,6 +4,7 @@ use pin_utils::unsafe_pinned;
 use romio::tcp::TcpStream;
 
 use std::io;
+use std::net::Shutdown;
 use std::pin::Pin;
 
 pub struct NetStream {
@@ -16,6 +17,18 @@ impl NetStream {
     pub(crate) fn new(stream: TcpStream) -> Self {
         Self { stream }
     }
+
+    /// Shuts down the write half of the connection, so the peer reads EOF
+    /// while this side can still read what the peer sends.
+    pub fn shutdown_write(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Write)
+    }
+
+    /// Shuts down the read half of the connection, while this side can still
+    /// write.
+    pub fn shutdown_read(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Read)
+    }
 }
 
 impl AsyncRead for NetStream {
@@ -45,3 +58,43 @@ impl AsyncWrite for NetStream {
         self.as_mut().stream().poll_close(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use futures::stream::StreamExt;
+    use romio::tcp::TcpListener;
+
+    use std::io::{Read, Write};
+    use std::thread;
+
+    #[test]
+    fn test_shutdown_write() {
+        let mut listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || -> io::Result<Vec<u8>> {
+            let mut stream = std::net::TcpStream::connect(addr)?;
+            let mut res = Vec::new();
+            stream.read_to_end(&mut res)?;
+            stream.write_all(b"ack")?;
+            Ok(res)
+        });
+
+        let result: io::Result<Vec<u8>> = block_on(async {
+            let stream = await!(listener.incoming().next()).unwrap()?;
+            let mut stream = NetStream::new(stream);
+            await!(stream.write_all(b"response"))?;
+            stream.shutdown_write()?;
+
+            let mut req = Vec::new();
+            await!(stream.read_to_end(&mut req))?;
+            Ok(req)
+        });
+
+        assert_eq!(client.join().unwrap().unwrap(), b"response");
+        assert_eq!(result.unwrap(), b"ack");
+    }
+}
@@ -0,0 +1,142 @@
+use crate::codec::LineCodec;
+use crate::handler::Handler;
+
+use futures::future::{BoxFuture, Future};
+use futures::io::{AsyncRead, AsyncWrite};
+
+use std::io;
+use std::sync::Arc;
+
+/// A handler of one request of a request/response protocol.
+pub trait RequestHandler {
+    type Future: Future<Output = String>;
+
+    fn handle(&self, req: String) -> Self::Future;
+}
+
+impl<F, Fut> RequestHandler for F
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = String> + Send + 'static,
+{
+    type Future = Fut;
+
+    fn handle(&self, req: String) -> Self::Future {
+        (self)(req)
+    }
+}
+
+/// Adapts a `RequestHandler` to a connection `Handler` of a line based
+/// protocol: each line read is a request, answered by a response line, until
+/// the peer closes the connection.
+pub struct LineProtocol<H> {
+    handler: Arc<H>,
+}
+
+impl<H> LineProtocol<H> {
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+impl<H, S> Handler<S> for LineProtocol<H>
+where
+    H: RequestHandler + Send + Sync + 'static,
+    H::Future: Send + 'static,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Future = BoxFuture<'static, io::Result<()>>;
+
+    fn handle(&self, stream: S) -> Self::Future {
+        let handler = self.handler.clone();
+        Box::pin(async move {
+            let mut codec = LineCodec::new(stream);
+            while let Some(req) = await!(codec.read_line())? {
+                let res = await!(handler.handle(req));
+                await!(codec.write_line(res))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+    use futures::task::{Context, Poll};
+
+    use std::pin::Pin;
+    use std::sync::Mutex;
+
+    struct MockStream {
+        input: &'static [u8],
+        output: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl AsyncRead for MockStream {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.input).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for MockStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.output.lock().unwrap().extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    async fn uppercase(req: String) -> String {
+        req.to_uppercase()
+    }
+
+    #[test]
+    fn test_line_protocol() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let stream = MockStream {
+            input: b"hello\r\nworld\nlast",
+            output: output.clone(),
+        };
+
+        let protocol = LineProtocol::new(uppercase);
+        let result = block_on(protocol.handle(stream));
+
+        assert!(result.is_ok());
+        assert_eq!(&*output.lock().unwrap(), b"HELLO\nWORLD\nLAST\n");
+    }
+
+    #[test]
+    fn test_line_protocol_invalid_utf8() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let stream = MockStream {
+            input: b"ok\n\xff\n",
+            output: output.clone(),
+        };
+
+        let protocol = LineProtocol::new(uppercase);
+        let result = block_on(protocol.handle(stream));
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(&*output.lock().unwrap(), b"OK\n");
+    }
+}
@@ -0,0 +1,24 @@
+//! CRUD repository.
+use uuid::Uuid;
+
+use crate::error::DbError;
+
+/// The CRUD methods of the table of `Model`, implemented for `PgConn` by
+/// `impl_crud_repository!`.
+///
+/// A connection implements it once per table, so a method is picked by the
+/// `Model` it returns, e.g. `let human: Option<Human> = conn.find_by_id(&id)?`,
+/// or named in full when nothing tells the tables apart, e.g.
+/// `CrudRepository::<Human>::delete_by_id(conn, &id)`.
+pub trait CrudRepository<Model> {
+    /// The `Insertable` input of `create`.
+    type Insert;
+
+    fn find_all(&self) -> Result<Vec<Model>, DbError>;
+
+    fn find_by_id(&self, id: &Uuid) -> Result<Option<Model>, DbError>;
+
+    fn create(&self, input: &Self::Insert) -> Result<Model, DbError>;
+
+    fn delete_by_id(&self, id: &Uuid) -> Result<usize, DbError>;
+}
@@ -1,6 +1,7 @@
 //! Error module.
 
 pub use diesel::r2d2::PoolError;
+pub use diesel::result::DatabaseErrorKind;
 pub use diesel::result::Error as DieselError;
 
 #[derive(Debug, Fail)]
@@ -10,11 +11,36 @@ pub enum DbError {
 
     #[fail(display = "pool error: {}", _0)]
     Pool(PoolError),
+
+    #[fail(display = "pool error: shutting down")]
+    ShuttingDown,
+
+    /// A row that was required to exist is missing, see `get_user`.
+    #[fail(display = "not found")]
+    NotFound,
+
+    /// A row conflicting with an existing one, e.g. a taken username.
+    #[fail(display = "conflict: {}", _0)]
+    Conflict(String),
+
+    #[fail(display = "password hash error: {}", _0)]
+    Password(String),
+
+    #[fail(display = "import error: {}", _0)]
+    Import(String),
+
+    #[fail(display = "invalid input: {}", _0)]
+    InvalidInput(String),
 }
 
 impl From<DieselError> for DbError {
     fn from(err: DieselError) -> Self {
-        DbError::Diesel(err)
+        match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                DbError::Conflict(info.message().to_owned())
+            }
+            err => DbError::Diesel(err),
+        }
     }
 }
 
@@ -2,16 +2,22 @@
 use diesel::prelude::*;
 use uuid::Uuid;
 
+use crate::crud::CrudRepository;
 use crate::error::DbError;
+use crate::page::{KeysetPaged, Page, Paged};
 use crate::pg::PgConn;
+use crate::pool::nested_transaction;
 use crate::schema::{human_friends, humans};
 
-#[derive(Debug, PartialEq, Queryable, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Queryable, Insertable, Deserialize, Serialize)]
+#[table_name = "humans"]
 pub struct Human {
     pub id: Uuid,
     pub name: String,
 }
 
+impl_crud_repository!(humans, Human, Human);
+
 #[derive(Deserialize)]
 pub struct CreateHuman {
     pub name: String,
@@ -34,48 +40,161 @@ struct HumanFriend<'a> {
 pub trait HumanRepository {
     fn find_humans(&self) -> Result<Vec<Human>, DbError>;
 
+    fn find_humans_paged(&self, page: Page) -> Result<Paged<Human>, DbError>;
+
+    /// Finds up to `limit` humans ordered by id, after the id `cursor` or
+    /// from the first one. A `limit` below 1 is treated as 1.
+    ///
+    /// Unlike `find_humans_paged` it doesn't get slower the further it
+    /// pages, as the rows before the cursor are skipped by the index.
+    fn find_humans_after(
+        &self,
+        cursor: Option<Uuid>,
+        limit: i64,
+    ) -> Result<KeysetPaged<Human, Uuid>, DbError>;
+
     fn find_human(&self, id: &Uuid) -> Result<Option<Human>, DbError>;
 
+    /// Like `find_human`, but a missing human is a `DbError::NotFound`.
+    fn get_human(&self, id: &Uuid) -> Result<Human, DbError> {
+        self.find_human(id)?.ok_or(DbError::NotFound)
+    }
+
     fn create_human(&self, input: CreateHuman) -> Result<Human, DbError>;
 
+    /// Like `create_human` for each of `inputs`, inserting the humans and
+    /// then their friends with one statement each.
+    ///
+    /// Returns the humans in the order of `inputs`. The friends must already
+    /// exist, so a human can't befriend another of the same batch.
+    fn create_humans(&self, inputs: Vec<CreateHuman>) -> Result<Vec<Human>, DbError>;
+
     fn update_human(&self, human_id: &Uuid, input: UpdateHuman) -> Result<Option<Human>, DbError>;
 
     fn delete_human(&self, human_id: &Uuid) -> Result<usize, DbError>;
 
     fn find_friends_by_human_id(&self, human_id: &Uuid) -> Result<Vec<Human>, DbError>;
+
+    /// Adds `friend_id` to the friends of `human_id`, returning the number of
+    /// rows inserted, 0 if they're already friends.
+    ///
+    /// A human can't befriend itself, and both humans must exist.
+    fn add_friend(&self, human_id: &Uuid, friend_id: &Uuid) -> Result<usize, DbError>;
+
+    /// Removes `friend_id` from the friends of `human_id`, returning the
+    /// number of rows deleted.
+    fn remove_friend(&self, human_id: &Uuid, friend_id: &Uuid) -> Result<usize, DbError>;
+
+    /// Like `add_friend`, for each of `friend_ids` in one statement.
+    fn add_friends(&self, human_id: &Uuid, friend_ids: &[Uuid]) -> Result<usize, DbError>;
+
+    /// Like `remove_friend`, for each of `friend_ids` in one statement.
+    fn remove_friends(&self, human_id: &Uuid, friend_ids: &[Uuid]) -> Result<usize, DbError>;
 }
 
 impl HumanRepository for PgConn {
     fn find_humans(&self) -> Result<Vec<Human>, DbError> {
-        use crate::schema::humans::dsl::*;
-        Ok(humans.load(self)?)
+        self.find_all()
+    }
+
+    fn find_humans_paged(&self, page: Page) -> Result<Paged<Human>, DbError> {
+        let total = humans::table.count().get_result(self)?;
+        let items = humans::table
+            .order(humans::id)
+            .limit(page.limit)
+            .offset(page.offset)
+            .load::<Human>(self)?;
+
+        Ok(Paged { items, total })
+    }
+
+    fn find_humans_after(
+        &self,
+        cursor: Option<Uuid>,
+        limit: i64,
+    ) -> Result<KeysetPaged<Human, Uuid>, DbError> {
+        let limit = limit.max(1);
+        let mut query = humans::table.order(humans::id).into_boxed();
+        if let Some(cursor) = cursor {
+            query = query.filter(humans::id.gt(cursor));
+        }
+        // one more row tells whether there's a next page
+        let mut items = query.limit(limit.saturating_add(1)).load::<Human>(self)?;
+        let next_cursor = if items.len() as i64 > limit {
+            items.truncate(limit as usize);
+            items.last().map(|human| human.id)
+        } else {
+            None
+        };
+
+        Ok(KeysetPaged { items, next_cursor })
     }
 
     fn find_human(&self, id: &Uuid) -> Result<Option<Human>, DbError> {
-        Ok(humans::table.find(id).get_result(self).optional()?)
+        self.find_by_id(id)
     }
 
     fn create_human(&self, input: CreateHuman) -> Result<Human, DbError> {
-        use crate::schema::humans::dsl::*;
+        nested_transaction(self, |conn| {
+            let human: Human = conn.create(&Human {
+                id: Uuid::new_v4(),
+                name: input.name,
+            })?;
+
+            let friends = input
+                .friend_ids
+                .iter()
+                .map(|friend_id| HumanFriend {
+                    human_id: &human.id,
+                    friend_id,
+                })
+                .collect::<Vec<HumanFriend>>();
+            diesel::insert_into(human_friends::table)
+                .values(&friends)
+                .execute(conn)?;
+
+            Ok(human)
+        })
+    }
 
-        let human_id = Uuid::new_v4();
-        let human = diesel::insert_into(humans)
-            .values((id.eq(&human_id), name.eq(&input.name)))
-            .get_result::<Human>(self)?;
+    fn create_humans(&self, inputs: Vec<CreateHuman>) -> Result<Vec<Human>, DbError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        let friends = input
-            .friend_ids
-            .iter()
-            .map(|friend_id| HumanFriend {
-                human_id: &human.id,
-                friend_id,
-            })
-            .collect::<Vec<HumanFriend>>();
-        diesel::insert_into(human_friends::table)
-            .values(&friends)
-            .execute(self)?;
+        nested_transaction(self, |conn| {
+            let (rows, friend_ids): (Vec<Human>, Vec<Vec<Uuid>>) = inputs
+                .into_iter()
+                .map(|input| {
+                    let human = Human {
+                        id: Uuid::new_v4(),
+                        name: input.name,
+                    };
+                    (human, input.friend_ids)
+                })
+                .unzip();
+            // the rows are returned as built, in the order of `inputs`, as
+            // `RETURNING` doesn't guarantee any order
+            diesel::insert_into(humans::table)
+                .values(&rows)
+                .execute(conn)?;
+
+            let friends = rows
+                .iter()
+                .zip(&friend_ids)
+                .flat_map(|(human, friend_ids)| {
+                    friend_ids.iter().map(move |friend_id| HumanFriend {
+                        human_id: &human.id,
+                        friend_id,
+                    })
+                })
+                .collect::<Vec<HumanFriend>>();
+            diesel::insert_into(human_friends::table)
+                .values(&friends)
+                .execute(conn)?;
 
-        Ok(human)
+            Ok(rows)
+        })
     }
 
     fn update_human(&self, human_id: &Uuid, input: UpdateHuman) -> Result<Option<Human>, DbError> {
@@ -109,17 +228,13 @@ impl HumanRepository for PgConn {
     }
 
     fn delete_human(&self, human_id: &Uuid) -> Result<usize, DbError> {
-        use crate::schema::humans::dsl::*;
-
         let _ = diesel::delete(human_friends::table)
             .filter(human_friends::friend_id.eq(human_id))
             .execute(self)?;
         let _ = diesel::delete(human_friends::table)
             .filter(human_friends::human_id.eq(human_id))
             .execute(self)?;
-        let updated = diesel::delete(humans.find(human_id)).execute(self)?;
-
-        Ok(updated)
+        CrudRepository::<Human>::delete_by_id(self, human_id)
     }
 
     fn find_friends_by_human_id(&self, human_id: &Uuid) -> Result<Vec<Human>, DbError> {
@@ -134,6 +249,48 @@ impl HumanRepository for PgConn {
             .filter(humans::id.eq(any(friend_ids)))
             .load(self)?)
     }
+
+    fn add_friend(&self, human_id: &Uuid, friend_id: &Uuid) -> Result<usize, DbError> {
+        self.add_friends(human_id, std::slice::from_ref(friend_id))
+    }
+
+    fn remove_friend(&self, human_id: &Uuid, friend_id: &Uuid) -> Result<usize, DbError> {
+        Ok(diesel::delete(human_friends::table.find((human_id, friend_id))).execute(self)?)
+    }
+
+    fn add_friends(&self, human_id: &Uuid, friend_ids: &[Uuid]) -> Result<usize, DbError> {
+        if friend_ids.contains(human_id) {
+            return Err(DbError::InvalidInput(
+                "a human can't befriend itself".to_owned(),
+            ));
+        }
+        if friend_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let friends = friend_ids
+            .iter()
+            .map(|friend_id| HumanFriend {
+                human_id,
+                friend_id,
+            })
+            .collect::<Vec<HumanFriend>>();
+        Ok(diesel::insert_into(human_friends::table)
+            .values(&friends)
+            .on_conflict_do_nothing()
+            .execute(self)?)
+    }
+
+    fn remove_friends(&self, human_id: &Uuid, friend_ids: &[Uuid]) -> Result<usize, DbError> {
+        use diesel::dsl::any;
+
+        Ok(diesel::delete(
+            human_friends::table
+                .filter(human_friends::human_id.eq(human_id))
+                .filter(human_friends::friend_id.eq(any(friend_ids))),
+        )
+        .execute(self)?)
+    }
 }
 
 #[cfg(test)]
@@ -147,12 +304,166 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_get_human_should_ok() {
+        let result = with_transaction(|conn| {
+            let human = conn.create_human(CreateHuman {
+                name: "alice".to_owned(),
+                friend_ids: vec![],
+            })?;
+            let found = conn.get_human(&human.id)?;
+            let missing = conn.get_human(&Uuid::new_v4());
+
+            Ok((human, found, missing))
+        });
+
+        assert_matches!(result, Ok((human, found, missing)) => {
+            assert_eq!(found, human);
+            assert_matches!(missing, Err(DbError::NotFound));
+        });
+    }
+
+    #[test]
+    fn test_create_humans_should_ok() {
+        let result = with_transaction(|conn| {
+            let a = conn.create_human(CreateHuman {
+                name: "a".to_owned(),
+                friend_ids: vec![],
+            })?;
+            let b = conn.create_human(CreateHuman {
+                name: "b".to_owned(),
+                friend_ids: vec![],
+            })?;
+            let created = conn.create_humans(vec![
+                CreateHuman {
+                    name: "x".to_owned(),
+                    friend_ids: vec![a.id],
+                },
+                CreateHuman {
+                    name: "y".to_owned(),
+                    friend_ids: vec![a.id, b.id],
+                },
+                CreateHuman {
+                    name: "z".to_owned(),
+                    friend_ids: vec![],
+                },
+            ])?;
+            let mut friends = Vec::new();
+            for human in &created {
+                let mut names = conn
+                    .find_friends_by_human_id(&human.id)?
+                    .into_iter()
+                    .map(|friend| friend.name)
+                    .collect::<Vec<_>>();
+                names.sort();
+                friends.push(names);
+            }
+            let persisted = created
+                .iter()
+                .map(|human| conn.find_human(&human.id))
+                .collect::<Result<Vec<_>, _>>()?;
+            let none = conn.create_humans(vec![])?;
+
+            Ok((created, friends, persisted, none))
+        });
+
+        assert_matches!(result, Ok((created, friends, persisted, none)) => {
+            let names = created.iter().map(|human| &human.name[..]).collect::<Vec<_>>();
+            assert_eq!(names, vec!["x", "y", "z"]);
+            assert_eq!(friends, vec![vec!["a"], vec!["a", "b"], vec![]]);
+            assert_eq!(persisted, created.into_iter().map(Some).collect::<Vec<_>>());
+            assert!(none.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_create_humans_unknown_friend_should_create_none() {
+        let result = with_transaction(|conn| {
+            let before = conn.find_humans()?.len();
+            let created = conn.create_humans(vec![
+                CreateHuman {
+                    name: "x".to_owned(),
+                    friend_ids: vec![],
+                },
+                CreateHuman {
+                    name: "y".to_owned(),
+                    friend_ids: vec![Uuid::new_v4()],
+                },
+            ]);
+            let after = conn.find_humans()?.len();
+
+            Ok((created, before, after))
+        });
+
+        assert_matches!(result, Ok((created, before, after)) => {
+            assert_matches!(created, Err(DbError::Diesel(_)));
+            assert_eq!(before, after);
+        });
+    }
+
     #[test]
     fn test_find_humans_should_ok() {
         let result = with_transaction(|conn| conn.find_humans());
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_find_humans_paged_should_ok() {
+        let result = with_transaction(|conn| {
+            for i in 0..5 {
+                conn.create_human(CreateHuman {
+                    name: format!("human{}", i),
+                    friend_ids: vec![],
+                })?;
+            }
+
+            let all = conn.find_humans_paged(Page::new(5, 0))?;
+            let page = conn.find_humans_paged(Page::new(2, 2))?;
+            let past_end = conn.find_humans_paged(Page::new(2, 10))?;
+
+            Ok((all, page, past_end))
+        });
+
+        assert_matches!(result, Ok((all, page, past_end)) => {
+            assert_eq!(all.total, 5);
+            assert_eq!(all.items.len(), 5);
+            assert_eq!(page.total, 5);
+            assert_eq!(page.items[..], all.items[2..4]);
+            assert_eq!(past_end.total, 5);
+            assert!(past_end.items.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_find_humans_after_should_ok() {
+        let result = with_transaction(|conn| {
+            for i in 0..5 {
+                conn.create_human(CreateHuman {
+                    name: format!("human{}", i),
+                    friend_ids: vec![],
+                })?;
+            }
+
+            let first = conn.find_humans_after(None, 3)?;
+            let second = conn.find_humans_after(first.next_cursor, 3)?;
+            let all = conn.find_humans_paged(Page::new(5, 0))?;
+            let negative = conn.find_humans_after(None, -1)?;
+            let unbounded = conn.find_humans_after(None, i64::MAX)?;
+
+            Ok((first, second, all, negative, unbounded))
+        });
+
+        assert_matches!(result, Ok((first, second, all, negative, unbounded)) => {
+            assert_eq!(first.items[..], all.items[..3]);
+            assert_eq!(first.next_cursor, Some(all.items[2].id));
+            assert_eq!(second.items[..], all.items[3..]);
+            assert_eq!(second.next_cursor, None);
+            assert_eq!(negative.items[..], all.items[..1]);
+            assert_eq!(unbounded.items, all.items);
+            assert_eq!(unbounded.next_cursor, None);
+        });
+    }
+
     #[test]
     fn test_create_human_should_ok() {
         let result = with_transaction(|conn| {
@@ -229,4 +540,107 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_add_and_remove_friend_should_ok() {
+        let result = with_transaction(|conn| {
+            let alice = conn.create_human(CreateHuman {
+                name: "alice".to_owned(),
+                friend_ids: vec![],
+            })?;
+            let carol = conn.create_human(CreateHuman {
+                name: "carol".to_owned(),
+                friend_ids: vec![],
+            })?;
+            let bob = conn.create_human(CreateHuman {
+                name: "bob".to_owned(),
+                friend_ids: vec![carol.id],
+            })?;
+
+            let added = conn.add_friend(&bob.id, &alice.id)?;
+            let added_again = conn.add_friend(&bob.id, &alice.id)?;
+            let with_alice = conn.find_friends_by_human_id(&bob.id)?.len();
+            let removed = conn.remove_friend(&bob.id, &alice.id)?;
+            let without_alice = conn.find_friends_by_human_id(&bob.id)?;
+
+            Ok((
+                added,
+                added_again,
+                with_alice,
+                removed,
+                without_alice,
+                carol,
+            ))
+        });
+
+        assert_matches!(result, Ok((added, added_again, with_alice, removed, without_alice, carol)) => {
+            assert_eq!(added, 1);
+            assert_eq!(added_again, 0);
+            assert_eq!(with_alice, 2);
+            assert_eq!(removed, 1);
+            assert_eq!(without_alice, vec![carol]);
+        });
+    }
+
+    #[test]
+    fn test_add_and_remove_friends_should_ok() {
+        let result = with_transaction(|conn| {
+            let mut others = Vec::new();
+            for name in &["alice", "bob", "carol"] {
+                others.push(conn.create_human(CreateHuman {
+                    name: (*name).to_owned(),
+                    friend_ids: vec![],
+                })?);
+            }
+            let ids = others.iter().map(|human| human.id).collect::<Vec<_>>();
+            let dave = conn.create_human(CreateHuman {
+                name: "dave".to_owned(),
+                friend_ids: vec![ids[0]],
+            })?;
+
+            let added = conn.add_friends(&dave.id, &ids)?;
+            let added_none = conn.add_friends(&dave.id, &[])?;
+            let removed = conn.remove_friends(&dave.id, &ids[..2])?;
+            let friends = conn.find_friends_by_human_id(&dave.id)?;
+            let self_friend = conn.add_friends(&dave.id, &[ids[1], dave.id]);
+
+            Ok((added, added_none, removed, friends, self_friend, others))
+        });
+
+        assert_matches!(result, Ok((added, added_none, removed, friends, self_friend, mut others)) => {
+            assert_eq!(added, 2);
+            assert_eq!(added_none, 0);
+            assert_eq!(removed, 2);
+            assert_eq!(friends, vec![others.remove(2)]);
+            assert_matches!(self_friend, Err(DbError::InvalidInput(_)));
+        });
+    }
+
+    #[test]
+    fn test_add_friend_guards() {
+        use crate::error::{DatabaseErrorKind, DieselError};
+
+        let result = with_transaction(|conn| {
+            let alice = conn.create_human(CreateHuman {
+                name: "alice".to_owned(),
+                friend_ids: vec![],
+            })?;
+            Ok(conn.add_friend(&alice.id, &alice.id))
+        });
+        assert_matches!(result, Ok(Err(DbError::InvalidInput(_))));
+
+        let result = with_transaction(|conn| {
+            let alice = conn.create_human(CreateHuman {
+                name: "alice".to_owned(),
+                friend_ids: vec![],
+            })?;
+            conn.add_friend(&alice.id, &Uuid::new_v4())
+        });
+        assert_matches!(
+            result,
+            Err(DbError::Diesel(DieselError::DatabaseError(
+                DatabaseErrorKind::ForeignKeyViolation,
+                _
+            )))
+        );
+    }
 }
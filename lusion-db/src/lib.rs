@@ -11,17 +11,29 @@ extern crate serde_derive;
 #[macro_use]
 extern crate assert_matches;
 
+#[macro_use]
+mod macros;
+
+pub mod crud;
 pub mod error;
 pub mod humans;
+pub mod page;
+pub mod password;
 pub mod pg;
 pub mod pool;
+pub mod read_write;
+pub mod retry;
+pub mod slow_log;
 pub mod test;
+#[cfg(feature = "trace")]
+pub mod traced;
 pub mod users;
 
 pub mod prelude {
     pub use crate::error::DbError;
+    pub use crate::password::PasswordHasher;
     pub use crate::pg::{PgConn, PgPool};
-    pub use crate::pool::DbPool;
+    pub use crate::pool::{DbPool, IsolationLevel};
 }
 
 mod schema;
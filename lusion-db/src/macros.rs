@@ -0,0 +1,175 @@
+//! Repository macros.
+
+/// Implements `CrudRepository<$model>` for `PgConn` with the CRUD methods of
+/// the table `$table`.
+///
+/// `$table` is the schema module of a table with an `Uuid` primary key,
+/// `$model` its `Queryable` row and `$insert` its `Insertable` input, see
+/// `Human` for an example.
+macro_rules! impl_crud_repository {
+    ($table:ident, $model:ty, $insert:ty) => {
+        impl $crate::crud::CrudRepository<$model> for $crate::pg::PgConn {
+            type Insert = $insert;
+
+            fn find_all(&self) -> Result<Vec<$model>, $crate::error::DbError> {
+                use diesel::prelude::*;
+
+                Ok($table::table.load::<$model>(self)?)
+            }
+
+            fn find_by_id(
+                &self,
+                id: &::uuid::Uuid,
+            ) -> Result<Option<$model>, $crate::error::DbError> {
+                use diesel::prelude::*;
+
+                Ok($table::table
+                    .find(id)
+                    .get_result::<$model>(self)
+                    .optional()?)
+            }
+
+            fn create(&self, input: &$insert) -> Result<$model, $crate::error::DbError> {
+                use diesel::prelude::*;
+
+                Ok(diesel::insert_into($table::table)
+                    .values(input)
+                    .get_result::<$model>(self)?)
+            }
+
+            fn delete_by_id(&self, id: &::uuid::Uuid) -> Result<usize, $crate::error::DbError> {
+                use diesel::prelude::*;
+
+                Ok(diesel::delete($table::table.find(id)).execute(self)?)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::prelude::*;
+    use uuid::Uuid;
+
+    use crate::crud::CrudRepository;
+    use crate::error::DbError;
+    use crate::pg::PgConn;
+    use crate::test_helpers::*;
+
+    table! {
+        notes (id) {
+            id -> Uuid,
+            body -> Text,
+        }
+    }
+
+    table! {
+        tags (id) {
+            id -> Uuid,
+            name -> Text,
+        }
+    }
+
+    #[derive(Debug, PartialEq, Queryable)]
+    struct Note {
+        id: Uuid,
+        body: String,
+    }
+
+    #[derive(Insertable)]
+    #[table_name = "notes"]
+    struct NewNote {
+        id: Uuid,
+        body: String,
+    }
+
+    #[derive(Debug, PartialEq, Queryable, Insertable)]
+    #[table_name = "tags"]
+    struct Tag {
+        id: Uuid,
+        name: String,
+    }
+
+    impl_crud_repository!(notes, Note, NewNote);
+    impl_crud_repository!(tags, Tag, Tag);
+
+    fn create_notes_table(conn: &PgConn) -> Result<(), DbError> {
+        diesel::sql_query("CREATE TEMPORARY TABLE notes (id UUID PRIMARY KEY, body TEXT NOT NULL)")
+            .execute(conn)?;
+        Ok(())
+    }
+
+    fn create_tags_table(conn: &PgConn) -> Result<(), DbError> {
+        diesel::sql_query("CREATE TEMPORARY TABLE tags (id UUID PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(conn)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_impl_crud_repository() {
+        let result = with_transaction(|conn| {
+            create_notes_table(conn)?;
+            let empty: Vec<Note> = conn.find_all()?;
+
+            let a: Note = conn.create(&NewNote {
+                id: Uuid::new_v4(),
+                body: "a".to_owned(),
+            })?;
+            let b: Note = conn.create(&NewNote {
+                id: Uuid::new_v4(),
+                body: "b".to_owned(),
+            })?;
+            let found: Option<Note> = conn.find_by_id(&a.id)?;
+            let all = CrudRepository::<Note>::find_all(conn)?.len();
+
+            let deleted = CrudRepository::<Note>::delete_by_id(conn, &a.id)?;
+            let deleted_again = CrudRepository::<Note>::delete_by_id(conn, &a.id)?;
+            let rest: Vec<Note> = conn.find_all()?;
+
+            Ok((empty, a, b, found, all, deleted, deleted_again, rest))
+        });
+
+        assert_matches!(result, Ok((empty, a, b, found, all, deleted, deleted_again, rest)) => {
+            assert!(empty.is_empty());
+            assert_eq!(a.body, "a");
+            assert_eq!(found, Some(a));
+            assert_eq!(all, 2);
+            assert_eq!(deleted, 1);
+            assert_eq!(deleted_again, 0);
+            assert_eq!(rest, vec![b]);
+        });
+    }
+
+    #[test]
+    fn test_impl_crud_repository_for_several_tables() {
+        let result = with_transaction(|conn| {
+            create_notes_table(conn)?;
+            create_tags_table(conn)?;
+            let id = Uuid::new_v4();
+            let note: Note = conn.create(&NewNote {
+                id,
+                body: "note".to_owned(),
+            })?;
+            let tag: Tag = conn.create(&Tag {
+                id,
+                name: "tag".to_owned(),
+            })?;
+
+            let found_note: Option<Note> = conn.find_by_id(&id)?;
+            let found_tag: Option<Tag> = conn.find_by_id(&id)?;
+            let deleted = CrudRepository::<Tag>::delete_by_id(conn, &id)?;
+            let notes: Vec<Note> = conn.find_all()?;
+            let tags: Vec<Tag> = conn.find_all()?;
+
+            Ok((note, tag, found_note, found_tag, deleted, notes, tags))
+        });
+
+        assert_matches!(result, Ok((note, tag, found_note, found_tag, deleted, notes, tags)) => {
+            assert_eq!(found_note, Some(note));
+            assert_eq!(found_tag, Some(tag));
+            assert_eq!(deleted, 1);
+            assert_eq!(notes.len(), 1);
+            assert!(tags.is_empty());
+        });
+    }
+}
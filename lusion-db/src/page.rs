@@ -0,0 +1,37 @@
+//! Pagination.
+
+/// A window of rows to load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Page {
+    pub fn new(limit: i64, offset: i64) -> Self {
+        Page { limit, offset }
+    }
+}
+
+/// The rows of a `Page` plus the total number of rows.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
+
+/// All the rows, as a single page.
+impl<T> From<Vec<T>> for Paged<T> {
+    fn from(items: Vec<T>) -> Self {
+        let total = items.len() as i64;
+        Paged { items, total }
+    }
+}
+
+/// The rows after a keyset cursor plus the cursor of the following rows,
+/// `None` after the last rows.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct KeysetPaged<T, C> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<C>,
+}
@@ -0,0 +1,190 @@
+//! Password hashing.
+use rand::RngCore;
+
+use crate::error::DbError;
+
+/// Hashes and verifies user passwords.
+///
+/// Hashes are stored in their encoded form, whose prefix (`$2b$`,
+/// `$argon2id$`, ...) identifies the algorithm. So `verify` accepts hashes of
+/// every supported algorithm, not only the one `hash` produces.
+pub trait PasswordHasher: Send + Sync + 'static {
+    /// Hash a password.
+    fn hash(&self, password: &str) -> Result<String, DbError>;
+
+    /// Verify a password against a hash of any supported algorithm.
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, DbError> {
+        verify(password, hash)
+    }
+
+    /// Whether `hash` was made by another algorithm or with weaker
+    /// parameters than this hasher uses, so the password should be re-hashed
+    /// once it's known.
+    fn needs_rehash(&self, hash: &str) -> bool;
+}
+
+/// Verify a password against a hash, picking the algorithm by its prefix.
+pub fn verify(password: &str, hash: &str) -> Result<bool, DbError> {
+    if hash.starts_with("$argon2") {
+        argon2::verify_encoded(hash, password.as_bytes()).map_err(password_error)
+    } else if hash.starts_with("$2") {
+        bcrypt::verify(password, hash).map_err(password_error)
+    } else {
+        Err(password_error("unknown password hash algorithm"))
+    }
+}
+
+fn password_error<E: ToString>(err: E) -> DbError {
+    DbError::Password(err.to_string())
+}
+
+/// A bcrypt `PasswordHasher`, the default.
+#[derive(Debug, Clone, Copy)]
+pub struct BcryptHasher {
+    pub cost: u32,
+}
+
+impl Default for BcryptHasher {
+    fn default() -> Self {
+        BcryptHasher {
+            cost: bcrypt::DEFAULT_COST,
+        }
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn hash(&self, password: &str) -> Result<String, DbError> {
+        bcrypt::hash(password, self.cost).map_err(password_error)
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        // $2b$<cost>$<salt and hash>
+        let cost = if hash.starts_with("$2") {
+            hash.split('$').nth(2).and_then(|cost| cost.parse().ok())
+        } else {
+            None
+        };
+        cost.map_or(true, |cost: u32| cost < self.cost)
+    }
+}
+
+/// An argon2id `PasswordHasher`.
+#[derive(Debug, Clone, Copy)]
+pub struct Argon2Hasher {
+    /// The memory cost in KiB.
+    pub mem_cost: u32,
+    /// The number of passes.
+    pub time_cost: u32,
+}
+
+impl Default for Argon2Hasher {
+    fn default() -> Self {
+        Argon2Hasher {
+            mem_cost: 4096,
+            time_cost: 3,
+        }
+    }
+}
+
+impl PasswordHasher for Argon2Hasher {
+    fn hash(&self, password: &str) -> Result<String, DbError> {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let config = argon2::Config {
+            variant: argon2::Variant::Argon2id,
+            mem_cost: self.mem_cost,
+            time_cost: self.time_cost,
+            ..argon2::Config::default()
+        };
+        argon2::hash_encoded(password.as_bytes(), &salt, &config).map_err(password_error)
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        // $argon2id$v=19$m=<mem_cost>,t=<time_cost>,p=<lanes>$<salt>$<hash>
+        if !hash.starts_with("$argon2id$") {
+            return true;
+        }
+        let params = hash.split('$').nth(3).unwrap_or("");
+        let param = |name: &str| {
+            params
+                .split(',')
+                .find(|param| param.starts_with(name))
+                .and_then(|param| param[name.len()..].parse::<u32>().ok())
+        };
+        param("m=").map_or(true, |m| m < self.mem_cost)
+            || param("t=").map_or(true, |t| t < self.time_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bcrypt_hasher() -> BcryptHasher {
+        BcryptHasher { cost: 4 }
+    }
+
+    fn argon2_hasher() -> Argon2Hasher {
+        Argon2Hasher {
+            mem_cost: 64,
+            time_cost: 1,
+        }
+    }
+
+    #[test]
+    fn test_bcrypt_hasher() {
+        let hasher = bcrypt_hasher();
+        let hash = hasher.hash("1234").unwrap();
+        assert!(hash.starts_with("$2"));
+        assert_matches!(hasher.verify("1234", &hash), Ok(true));
+        assert_matches!(hasher.verify("4321", &hash), Ok(false));
+    }
+
+    #[test]
+    fn test_argon2_hasher() {
+        let hasher = argon2_hasher();
+        let hash = hasher.hash("1234").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert_matches!(hasher.verify("1234", &hash), Ok(true));
+        assert_matches!(hasher.verify("4321", &hash), Ok(false));
+    }
+
+    #[test]
+    fn test_argon2_hasher_verifies_bcrypt_hash() {
+        let hash = bcrypt_hasher().hash("1234").unwrap();
+        let hasher = argon2_hasher();
+        assert_matches!(hasher.verify("1234", &hash), Ok(true));
+        assert_matches!(hasher.verify("4321", &hash), Ok(false));
+    }
+
+    #[test]
+    fn test_bcrypt_hasher_needs_rehash() {
+        let hash = bcrypt_hasher().hash("1234").unwrap();
+        assert!(!bcrypt_hasher().needs_rehash(&hash));
+        assert!(!BcryptHasher { cost: 3 }.needs_rehash(&hash));
+        assert!(BcryptHasher { cost: 5 }.needs_rehash(&hash));
+
+        let hash = argon2_hasher().hash("1234").unwrap();
+        assert!(bcrypt_hasher().needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_argon2_hasher_needs_rehash() {
+        let hash = argon2_hasher().hash("1234").unwrap();
+        assert!(!argon2_hasher().needs_rehash(&hash));
+
+        let stronger = Argon2Hasher {
+            mem_cost: 128,
+            time_cost: 1,
+        };
+        assert!(stronger.needs_rehash(&hash));
+
+        let hash = bcrypt_hasher().hash("1234").unwrap();
+        assert!(argon2_hasher().needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_verify_unknown_algorithm() {
+        assert_matches!(verify("1234", "1234"), Err(DbError::Password(_)));
+    }
+}
@@ -1,24 +1,303 @@
 //! PostgreSQL module.
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use diesel::connection::{SimpleConnection, TransactionManager};
 use diesel::pg::PgConnection;
-use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, Pool, PooledConnection};
+use diesel::sql_types::Text;
 
 use crate::error::DbError;
-use crate::pool::DbPool;
+use crate::password::{BcryptHasher, PasswordHasher};
+use crate::pool::{DbPool, PoolState};
+use crate::retry::Backoff;
 
 /// A PostgreSQL connection.
 pub type PgConn = PgConnection;
 
 /// A PostgreSQL connection pool.
 #[derive(Clone)]
-pub struct PgPool(Pool<ConnectionManager<PgConn>>);
+pub struct PgPool {
+    pool: Pool<ConnectionManager<PgConn>>,
+    password_hasher: Arc<dyn PasswordHasher>,
+    acquire_backoff: Backoff,
+    draining: Arc<AtomicBool>,
+}
 
 impl PgPool {
     pub fn new(database_url: &str) -> Result<Self, DbError> {
+        Self::builder().build(database_url)
+    }
+
+    pub fn builder() -> PgPoolBuilder {
+        PgPoolBuilder::default()
+    }
+
+    /// Checks out a connection whose transactions the caller begins, commits
+    /// and rolls back explicitly.
+    ///
+    /// Prefer `with` and `transaction`; this is for flows that keep using
+    /// the connection after a transaction, e.g. with session-level temporary
+    /// tables.
+    pub fn checkout(&self) -> Result<PgConnGuard, DbError> {
+        Ok(PgConnGuard(self.get()?))
+    }
+
+    fn get(&self) -> Result<PooledConnection<ConnectionManager<PgConn>>, DbError> {
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(DbError::ShuttingDown);
+        }
+        let timeout = self.pool.connection_timeout();
+        let deadline = Instant::now() + timeout;
+        // the retried connect attempts share the connection timeout
+        let attempt_timeout = timeout / (self.acquire_backoff.retries + 1);
+        self.acquire_backoff
+            .retry_if(
+                || {
+                    let now = Instant::now();
+                    let remaining = if deadline > now {
+                        deadline - now
+                    } else {
+                        Duration::from_millis(0)
+                    };
+                    // waiting for a connection to be returned isn't retried,
+                    // so it gets the rest of the timeout
+                    if self.exhausted() {
+                        self.pool.get_timeout(remaining)
+                    } else {
+                        self.pool.get_timeout(attempt_timeout.min(remaining))
+                    }
+                },
+                // an exhausted pool isn't retried, only failing to connect
+                |_| !self.exhausted(),
+            )
+            .map_err(DbError::Pool)
+    }
+
+    /// Whether the pool has opened all the connections it may.
+    fn exhausted(&self) -> bool {
+        self.pool.state().connections >= self.pool.max_size()
+    }
+
+    /// Stops handing out connections and waits up to `timeout` for the
+    /// checked out ones to be returned.
+    ///
+    /// Every clone of the pool is drained, so calls through them fail with
+    /// `DbError::ShuttingDown`. Returns whether all connections were returned
+    /// in time.
+    pub fn drain(self, timeout: Duration) -> bool {
+        self.draining.store(true, Ordering::SeqCst);
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let state = self.pool.state();
+            if state.idle_connections == state.connections {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                log::warn!(
+                    "pool drain timed out with {} connections in use",
+                    state.connections - state.idle_connections
+                );
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// A connection checked out of a `PgPool` by `checkout`.
+///
+/// When the guard is dropped, a transaction still open is rolled back and
+/// the temporary tables are discarded before the connection returns to the
+/// pool. Only `DISCARD TEMP` is run, as `DISCARD ALL` would also deallocate
+/// the statements diesel has prepared and reset the session settings it
+/// made when connecting.
+pub struct PgConnGuard(PooledConnection<ConnectionManager<PgConn>>);
+
+impl PgConnGuard {
+    /// Begins a transaction, or a savepoint inside an open one.
+    pub fn begin(&self) -> Result<(), DbError> {
+        Ok(self.0.transaction_manager().begin_transaction(&*self.0)?)
+    }
+
+    /// Commits the innermost open transaction.
+    pub fn commit(&self) -> Result<(), DbError> {
+        Ok(self.0.transaction_manager().commit_transaction(&*self.0)?)
+    }
+
+    /// Rolls back the innermost open transaction.
+    pub fn rollback(&self) -> Result<(), DbError> {
+        Ok(self
+            .0
+            .transaction_manager()
+            .rollback_transaction(&*self.0)?)
+    }
+
+    fn transaction_depth(&self) -> u32 {
+        TransactionManager::<PgConn>::get_transaction_depth(self.0.transaction_manager())
+    }
+}
+
+impl Deref for PgConnGuard {
+    type Target = PgConn;
+
+    fn deref(&self) -> &PgConn {
+        &self.0
+    }
+}
+
+impl Drop for PgConnGuard {
+    fn drop(&mut self) {
+        while self.transaction_depth() > 0 {
+            if let Err(err) = self.rollback() {
+                log::warn!("failed to roll back a dropped connection: {}", err);
+                return;
+            }
+        }
+        if let Err(err) = self.0.batch_execute("DISCARD TEMP") {
+            log::warn!("failed to discard a dropped connection: {}", err);
+        }
+    }
+}
+
+/// The maximum size of a pool, as in r2d2.
+const DEFAULT_MAX_SIZE: u32 = 10;
+
+/// A `PgPool` builder.
+#[derive(Default)]
+pub struct PgPoolBuilder {
+    max_size: Option<u32>,
+    min_idle: Option<u32>,
+    connection_timeout: Option<Duration>,
+    warm_up: Option<u32>,
+    password_hasher: Option<Arc<dyn PasswordHasher>>,
+    session_params: Vec<(String, String)>,
+    acquire_backoff: Backoff,
+}
+
+impl PgPoolBuilder {
+    /// Sets the maximum number of connections, 10 by default.
+    pub fn max_size(mut self, max_size: u32) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Sets the number of idle connections the pool tries to keep, up to
+    /// the maximum size by default.
+    pub fn min_idle(mut self, min_idle: Option<u32>) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    /// Sets how long getting a connection waits for one to become
+    /// available before failing, 30 seconds by default.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = Some(timeout);
+        self
+    }
+
+    /// Checks out `n` connections at once when the pool is built, so the
+    /// first requests don't pay the connection-establishment latency.
+    ///
+    /// The pool already opens its `min_idle` connections when built, which
+    /// is the maximum size by default, so this only matters with a smaller
+    /// `min_idle`; the connections above it are closed again once idle.
+    /// `build` fails with `DbError::InvalidInput` if `n` is larger than the
+    /// maximum size.
+    pub fn warm_up(mut self, n: u32) -> Self {
+        self.warm_up = Some(n);
+        self
+    }
+
+    /// Sets the hasher for new user passwords, `BcryptHasher` by default.
+    ///
+    /// Hashes of the other supported algorithms keep verifying after a
+    /// switch.
+    pub fn password_hasher<H: PasswordHasher>(mut self, hasher: H) -> Self {
+        self.password_hasher = Some(Arc::new(hasher));
+        self
+    }
+
+    /// Sets session parameters, e.g. `timezone`, `application_name` or
+    /// `search_path`, on every new connection.
+    pub fn on_acquire(mut self, params: Vec<(String, String)>) -> Self {
+        self.session_params = params;
+        self
+    }
+
+    /// Retries getting a connection `retries` times, waiting `base_delay`
+    /// before the first retry and doubling it after each, so that brief
+    /// database restarts don't fail requests.
+    ///
+    /// Only failures to open a connection are retried, not waiting for one
+    /// in an exhausted pool, which waits the whole connection timeout. The
+    /// connect attempts split the connection timeout and no retry starts
+    /// after it, so a call still gives up after about the connection timeout
+    /// either way.
+    pub fn acquire_retries(mut self, retries: u32, base_delay: Duration) -> Self {
+        self.acquire_backoff = Backoff::new(retries, base_delay);
+        self
+    }
+
+    /// Builds the pool, blocking until the warm-up connections are open.
+    pub fn build(self, database_url: &str) -> Result<PgPool, DbError> {
         log::debug!("initialize database: {}", database_url);
 
         let manager = ConnectionManager::<PgConn>::new(database_url);
-        let pool = Pool::new(manager)?;
-        Ok(PgPool(pool))
+        let mut builder = Pool::builder();
+        if !self.session_params.is_empty() {
+            builder = builder.connection_customizer(Box::new(SessionParams(self.session_params)));
+        }
+        let max_size = self.max_size.unwrap_or(DEFAULT_MAX_SIZE);
+        let warm_up = self.warm_up.unwrap_or(0);
+        if warm_up > max_size {
+            return Err(DbError::InvalidInput(format!(
+                "cannot warm up {} connections in a pool of {}",
+                warm_up, max_size
+            )));
+        }
+        builder = builder.max_size(max_size).min_idle(self.min_idle);
+        if let Some(timeout) = self.connection_timeout {
+            builder = builder.connection_timeout(timeout);
+        }
+        let pool = builder.build(manager)?;
+        // checked out together, so each one is a separate connection
+        let warmed = (0..warm_up)
+            .map(|_| pool.get())
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(warmed);
+        let password_hasher = self
+            .password_hasher
+            .unwrap_or_else(|| Arc::new(BcryptHasher::default()));
+        let acquire_backoff = self.acquire_backoff.max_elapsed(pool.connection_timeout());
+        Ok(PgPool {
+            pool,
+            password_hasher,
+            acquire_backoff,
+            draining: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
+
+/// Sets the session parameters on the connections when they're established.
+#[derive(Debug)]
+struct SessionParams(Vec<(String, String)>);
+
+impl CustomizeConnection<PgConn, r2d2::Error> for SessionParams {
+    fn on_acquire(&self, conn: &mut PgConn) -> Result<(), r2d2::Error> {
+        for (name, value) in &self.0 {
+            diesel::sql_query("select set_config($1, $2, false)")
+                .bind::<Text, _>(name)
+                .bind::<Text, _>(value)
+                .execute(conn)
+                .map_err(r2d2::Error::QueryError)?;
+        }
+        Ok(())
     }
 }
 
@@ -29,15 +308,27 @@ impl DbPool for PgPool {
     where
         F: FnOnce(&Self::Connection) -> Result<T, DbError>,
     {
-        let conn = self.0.get().map_err(DbError::Pool)?;
+        let conn = self.get()?;
         f(&conn)
     }
+
+    fn state(&self) -> Option<PoolState> {
+        let state = self.pool.state();
+        Some(PoolState {
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+        })
+    }
+
+    fn password_hasher(&self) -> &dyn PasswordHasher {
+        &*self.password_hasher
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use diesel::connection::SimpleConnection;
+    use crate::test_helpers::take_log_records;
 
     #[test]
     fn test_pg_pool() {
@@ -48,4 +339,291 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_pg_pool_warm_up() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder().warm_up(2).build(&database_url).unwrap();
+
+        assert_matches!(pool.state(), Some(state) => {
+            assert!(state.idle_connections >= 2);
+        });
+    }
+
+    #[test]
+    fn test_pg_pool_warm_up_above_min_idle() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .max_size(4)
+            .min_idle(Some(0))
+            .warm_up(3)
+            .build(&database_url)
+            .unwrap();
+
+        assert_matches!(pool.state(), Some(state) => {
+            assert!(state.connections >= 3);
+        });
+    }
+
+    #[test]
+    fn test_pg_pool_warm_up_above_max_size() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let result = PgPool::builder()
+            .max_size(2)
+            .warm_up(3)
+            .build(&database_url);
+        assert_matches!(result.err(), Some(DbError::InvalidInput(_)));
+
+        let result = PgPool::builder().warm_up(11).build(&database_url);
+        assert_matches!(result.err(), Some(DbError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_pg_pool_max_size() {
+        use std::sync::Mutex;
+
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .max_size(1)
+            .min_idle(Some(1))
+            .connection_timeout(Duration::from_secs(5))
+            .build(&database_url)
+            .unwrap();
+        let spans = Arc::new(Mutex::new(Vec::new()));
+
+        let handles = (0..2)
+            .map(|_| {
+                let pool = pool.clone();
+                let spans = spans.clone();
+                thread::spawn(move || {
+                    pool.with(|_| {
+                        let start = Instant::now();
+                        thread::sleep(Duration::from_millis(100));
+                        spans.lock().unwrap().push((start, Instant::now()));
+                        Ok(())
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert!(handle.join().unwrap().is_ok());
+        }
+
+        let mut spans = spans.lock().unwrap();
+        spans.sort();
+        assert_eq!(spans.len(), 2);
+        assert!(spans[0].1 <= spans[1].0);
+        assert_matches!(pool.state(), Some(state) => {
+            assert_eq!(state.connections, 1);
+        });
+    }
+
+    #[test]
+    fn test_pg_pool_connection_timeout() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(50))
+            .build(&database_url)
+            .unwrap();
+
+        let result = pool.with(|_| Ok(pool.with(|_| Ok(()))));
+        assert_matches!(result, Ok(Err(DbError::Pool(_))));
+    }
+
+    #[test]
+    fn test_pg_pool_password_hasher() {
+        use crate::password::Argon2Hasher;
+
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .password_hasher(Argon2Hasher::default())
+            .build(&database_url)
+            .unwrap();
+        let hash = pool.password_hasher().hash("1234").unwrap();
+
+        assert!(hash.starts_with("$argon2id$"));
+    }
+
+    #[test]
+    fn test_pg_pool_on_acquire() {
+        use diesel::dsl::sql;
+
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .on_acquire(vec![
+                ("application_name".to_owned(), "lusion-test".to_owned()),
+                ("timezone".to_owned(), "Asia/Shanghai".to_owned()),
+            ])
+            .build(&database_url)
+            .unwrap();
+        let result = pool.with(|conn| {
+            let name = diesel::select(sql::<Text>("current_setting('application_name')"))
+                .get_result::<String>(conn)?;
+            let timezone = diesel::select(sql::<Text>("current_setting('timezone')"))
+                .get_result::<String>(conn)?;
+            Ok((name, timezone))
+        });
+
+        assert_matches!(result, Ok((name, timezone)) => {
+            assert_eq!(name, "lusion-test");
+            assert_eq!(timezone, "Asia/Shanghai");
+        });
+    }
+
+    #[test]
+    fn test_pg_pool_acquire_retries() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .acquire_retries(3, Duration::from_millis(10))
+            .build(&database_url)
+            .unwrap();
+        let result = pool.with(|conn| Ok(conn.batch_execute("select 1")?));
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_pg_pool_checkout() {
+        use crate::humans::{CreateHuman, HumanRepository};
+
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        // a single connection, so the guards below get the same session
+        let pool = PgPool::builder().max_size(1).build(&database_url).unwrap();
+        let name = format!("checkout-{}", uuid::Uuid::new_v4());
+        let create = |conn: &PgConn, suffix: &str| {
+            conn.create_human(CreateHuman {
+                name: format!("{}-{}", name, suffix),
+                friend_ids: Vec::new(),
+            })
+        };
+        let persisted = |pool: &PgPool| {
+            let mut names = pool
+                .with(|conn| conn.find_humans())
+                .unwrap()
+                .into_iter()
+                .filter(|human| human.name.starts_with(&name))
+                .map(|human| human.name)
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+
+        // deletes the committed humans even if an assertion fails
+        struct Cleanup<'a>(&'a PgPool, &'a str);
+        impl<'a> Drop for Cleanup<'a> {
+            fn drop(&mut self) {
+                let _ = self.0.with(|conn| {
+                    for human in conn.find_humans()? {
+                        if human.name.starts_with(self.1) {
+                            conn.delete_human(&human.id)?;
+                        }
+                    }
+                    Ok(())
+                });
+            }
+        }
+        let _cleanup = Cleanup(&pool, &name);
+
+        let conn = pool.checkout().unwrap();
+        conn.batch_execute("CREATE TEMPORARY TABLE checkout (id INTEGER)")
+            .unwrap();
+        conn.begin().unwrap();
+        create(&conn, "committed").unwrap();
+        conn.commit().unwrap();
+        conn.begin().unwrap();
+        create(&conn, "rolled-back").unwrap();
+        conn.rollback().unwrap();
+        conn.begin().unwrap();
+        create(&conn, "dropped").unwrap();
+        // the session outlives the transactions
+        conn.batch_execute("INSERT INTO checkout VALUES (1)")
+            .unwrap();
+        drop(conn);
+
+        assert_eq!(persisted(&pool), vec![format!("{}-committed", name)]);
+        let conn = pool.checkout().unwrap();
+        assert!(conn.batch_execute("SELECT * FROM checkout").is_err());
+    }
+
+    #[test]
+    fn test_pg_pool_acquire_retries_unreachable() {
+        let pool = PgPool::builder()
+            .min_idle(Some(0))
+            .connection_timeout(Duration::from_millis(300))
+            .acquire_retries(2, Duration::from_millis(10))
+            .build("postgres://postgres@localhost:1/lusion")
+            .unwrap();
+
+        take_log_records();
+        let start = Instant::now();
+        let result = pool.with(|_| Ok(()));
+        let elapsed = start.elapsed();
+        assert_matches!(result, Err(DbError::Pool(_)));
+        let retries = take_log_records()
+            .into_iter()
+            .filter(|(_, message)| message.starts_with("attempt "))
+            .count();
+        assert_eq!(retries, 2);
+        // three attempts of 100ms, not of the whole 300ms timeout
+        assert!(elapsed < Duration::from_millis(600), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_pg_pool_acquire_retries_exhausted() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(300))
+            .acquire_retries(2, Duration::from_millis(10))
+            .build(&database_url)
+            .unwrap();
+
+        take_log_records();
+        let start = Instant::now();
+        let result = pool.with(|_| Ok(pool.with(|_| Ok(()))));
+        let elapsed = start.elapsed();
+        assert_matches!(result, Ok(Err(DbError::Pool(_))));
+        // a single attempt, as retrying can't open another connection, which
+        // waits the whole timeout
+        assert!(take_log_records().is_empty());
+        assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(600), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_pg_pool_drain() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+        let handle = pool.clone();
+        let result = handle.with(|conn| Ok(conn.batch_execute("select 1")?));
+        assert!(result.is_ok());
+
+        assert!(pool.drain(Duration::from_secs(1)));
+
+        let result = handle.with(|conn| Ok(conn.batch_execute("select 1")?));
+        assert_matches!(result, Err(DbError::ShuttingDown));
+    }
+
+    #[test]
+    fn test_pg_pool_drain_timeout() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+        let handle = pool.clone();
+
+        let drained = handle.with(|_| Ok(pool.drain(Duration::from_millis(50))));
+        assert_matches!(drained, Ok(false));
+    }
 }
@@ -1,6 +1,65 @@
-use diesel::connection::{Connection, TransactionManager};
+use diesel::connection::{Connection, SimpleConnection, TransactionManager};
 
 use crate::error::DbError;
+use crate::password::{BcryptHasher, PasswordHasher};
+
+static DEFAULT_PASSWORD_HASHER: BcryptHasher = BcryptHasher {
+    cost: bcrypt::DEFAULT_COST,
+};
+
+/// Information about the state of a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolState {
+    /// The number of connections currently being managed by the pool.
+    pub connections: u32,
+    /// The number of idle connections.
+    pub idle_connections: u32,
+}
+
+/// The isolation level of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    pub(crate) fn as_sql(self) -> &'static str {
+        match self {
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// Executes `f` inside of a transaction on `conn`, or inside of a savepoint
+/// when a transaction is already open.
+///
+/// Diesel tracks the transaction depth of the connection and issues
+/// `SAVEPOINT`, `RELEASE SAVEPOINT` and `ROLLBACK TO SAVEPOINT` when nested,
+/// so a failing `f` only rolls back its own changes and leaves the outer
+/// transaction usable. Repository methods writing several rows use this to
+/// stay atomic whether or not they're called inside a transaction.
+pub fn nested_transaction<C, F, T>(conn: &C, f: F) -> Result<T, DbError>
+where
+    C: Connection,
+    F: FnOnce(&C) -> Result<T, DbError>,
+{
+    let transaction_manager = conn.transaction_manager();
+    transaction_manager.begin_transaction(conn)?;
+    match f(conn) {
+        Ok(value) => {
+            transaction_manager.commit_transaction(conn)?;
+            Ok(value)
+        }
+        Err(e) => {
+            transaction_manager.rollback_transaction(conn)?;
+            Err(e)
+        }
+    }
+}
 
 /// A database connection pool.
 pub trait DbPool {
@@ -12,23 +71,299 @@ pub trait DbPool {
         F: FnOnce(&Self::Connection) -> Result<T, DbError>;
 
     /// Executes the given function inside of a database transaction
+    ///
+    /// Calls of `nested_transaction` on the connection inside `f` run in
+    /// savepoints.
     fn transaction<F, T>(&self, f: F) -> Result<T, DbError>
     where
         F: FnOnce(&Self::Connection) -> Result<T, DbError>,
     {
-        self.with(|conn| {
-            let transaction_manager = conn.transaction_manager();
-            transaction_manager.begin_transaction(conn)?;
-            match f(&conn) {
-                Ok(value) => {
-                    transaction_manager.commit_transaction(conn)?;
-                    Ok(value)
-                }
-                Err(e) => {
-                    transaction_manager.rollback_transaction(conn)?;
-                    Err(e)
+        self.with(|conn| nested_transaction(conn, f))
+    }
+
+    /// Like `transaction`, but sets the isolation level of the transaction
+    /// to `level` before executing `f`.
+    ///
+    /// The level can only be set before the first query of a transaction, so
+    /// this fails when called from inside another transaction.
+    fn transaction_with<F, T>(&self, level: IsolationLevel, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        self.transaction(|conn| {
+            conn.batch_execute(&format!(
+                "SET TRANSACTION ISOLATION LEVEL {}",
+                level.as_sql()
+            ))?;
+            f(conn)
+        })
+    }
+
+    /// Like `transaction`, but hands the error that made `f` roll the
+    /// transaction back to `on_rollback`, which may log it or replace it.
+    ///
+    /// Errors beginning or committing the transaction are returned as is.
+    fn transaction_on_rollback<F, R, T>(&self, f: F, on_rollback: R) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+        R: FnOnce(DbError) -> DbError,
+    {
+        let mut rolled_back = false;
+        let result = self.transaction(|conn| {
+            f(conn).map_err(|err| {
+                rolled_back = true;
+                err
+            })
+        });
+        match result {
+            Err(err) if rolled_back => Err(on_rollback(err)),
+            result => result,
+        }
+    }
+
+    /// Executes `f` for each of `items`, committing a transaction after every
+    /// `chunk_size` items.
+    ///
+    /// This intentionally gives up all-or-nothing atomicity to avoid holding
+    /// one huge transaction and its locks: when `f` fails, the chunk being
+    /// processed is rolled back but the earlier chunks stay committed.
+    /// Returns the number of items processed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    fn chunked_transaction<I, F>(
+        &self,
+        items: I,
+        chunk_size: usize,
+        mut f: F,
+    ) -> Result<usize, DbError>
+    where
+        I: IntoIterator,
+        F: FnMut(&Self::Connection, I::Item) -> Result<(), DbError>,
+    {
+        assert!(chunk_size > 0, "chunk_size must be positive");
+
+        let mut items = items.into_iter().peekable();
+        let mut count = 0;
+        while items.peek().is_some() {
+            let chunk = items.by_ref().take(chunk_size).collect::<Vec<_>>();
+            count += self.transaction(|conn| {
+                let len = chunk.len();
+                for item in chunk {
+                    f(conn, item)?;
                 }
+                Ok(len)
+            })?;
+        }
+        Ok(count)
+    }
+
+    /// Returns information about the current state of the pool, if known.
+    fn state(&self) -> Option<PoolState> {
+        None
+    }
+
+    /// Returns the hasher for new user passwords, bcrypt by default.
+    fn password_hasher(&self) -> &dyn PasswordHasher {
+        &DEFAULT_PASSWORD_HASHER
+    }
+
+    /// Checks that the database is reachable by running `SELECT 1`.
+    ///
+    /// It needs a connection like any other call, so when the pool is
+    /// exhausted it waits for one to be returned and fails once the pool's
+    /// connection timeout elapses, with `DbError::Pool` for a `PgPool`. A
+    /// busy pool may therefore fail a readiness probe.
+    fn health_check(&self) -> Result<(), DbError> {
+        self.with(|conn| Ok(conn.batch_execute("SELECT 1")?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::humans::{CreateHuman, HumanRepository};
+    use crate::pg::{PgConn, PgPool};
+    use crate::test::TestPool;
+
+    #[test]
+    fn test_health_check() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+
+        assert_matches!(pool.health_check(), Ok(()));
+        assert_matches!(TestPool::with(pool).health_check(), Ok(()));
+    }
+
+    #[test]
+    fn test_health_check_exhausted_pool() {
+        use std::time::Duration;
+
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(50))
+            .build(&database_url)
+            .unwrap();
+
+        let result = pool.with(|_| Ok(pool.health_check()));
+        assert_matches!(result, Ok(Err(DbError::Pool(_))));
+        assert_matches!(pool.health_check(), Ok(()));
+    }
+
+    #[test]
+    fn test_transaction_with_isolation_level() {
+        use diesel::dsl::sql;
+        use diesel::sql_types::Text;
+        use diesel::RunQueryDsl;
+
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+        let current_level = |conn: &PgConn| {
+            Ok(
+                diesel::select(sql::<Text>("current_setting('transaction_isolation')"))
+                    .get_result::<String>(conn)?,
+            )
+        };
+
+        let result = pool.transaction_with(IsolationLevel::Serializable, current_level);
+        assert_matches!(result, Ok(ref level) if level == "serializable");
+        let result = pool.transaction_with(IsolationLevel::RepeatableRead, current_level);
+        assert_matches!(result, Ok(ref level) if level == "repeatable read");
+        let result = pool.transaction(current_level);
+        assert_matches!(result, Ok(ref level) if level == "read committed");
+    }
+
+    #[test]
+    fn test_nested_transaction() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+        let prefix = format!("nested-{}", uuid::Uuid::new_v4());
+        let create = |conn: &PgConn, suffix: &str| {
+            conn.create_human(CreateHuman {
+                name: format!("{}-{}", prefix, suffix),
+                friend_ids: Vec::new(),
+            })
+        };
+
+        let result = pool.transaction(|conn| {
+            create(conn, "outer")?;
+            let inner = nested_transaction(conn, |conn| {
+                create(conn, "inner")?;
+                let innermost = nested_transaction(conn, |conn| {
+                    create(conn, "innermost")?;
+                    Err::<(), _>(DbError::InvalidInput("abort".to_owned()))
+                });
+                assert_matches!(innermost, Err(DbError::InvalidInput(_)));
+                Err::<(), _>(DbError::InvalidInput("abort".to_owned()))
+            });
+            assert_matches!(inner, Err(DbError::InvalidInput(_)));
+            // an unknown friend fails the insert of the friend rows, which
+            // rolls back the human too
+            let unknown_friend = conn.create_human(CreateHuman {
+                name: format!("{}-unknown-friend", prefix),
+                friend_ids: vec![uuid::Uuid::new_v4()],
+            });
+            assert_matches!(unknown_friend, Err(DbError::Diesel(_)));
+            create(conn, "after")?;
+            Ok(())
+        });
+
+        let persisted = pool
+            .with(|conn| conn.find_humans())
+            .unwrap()
+            .into_iter()
+            .filter(|human| human.name.starts_with(&prefix))
+            .collect::<Vec<_>>();
+        for human in &persisted {
+            pool.with(|conn| conn.delete_human(&human.id)).unwrap();
+        }
+
+        assert_matches!(result, Ok(()));
+        let mut names = persisted
+            .into_iter()
+            .map(|human| human.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![format!("{}-after", prefix), format!("{}-outer", prefix)]
+        );
+    }
+
+    #[test]
+    fn test_transaction_on_rollback() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+        let name = format!("rollback-{}", uuid::Uuid::new_v4());
+
+        let mut rolled_back = None;
+        let result = pool.transaction_on_rollback(
+            |conn| {
+                conn.create_human(CreateHuman {
+                    name: name.clone(),
+                    friend_ids: Vec::new(),
+                })?;
+                Err::<(), _>(DbError::InvalidInput("abort".to_owned()))
+            },
+            |err| {
+                rolled_back = Some(err.to_string());
+                DbError::InvalidInput("aborted".to_owned())
+            },
+        );
+
+        assert_matches!(result, Err(DbError::InvalidInput(ref msg)) if msg == "aborted");
+        assert_eq!(rolled_back, Some("invalid input: abort".to_owned()));
+        let persisted = pool.with(|conn| conn.find_humans()).unwrap();
+        assert!(persisted.iter().all(|human| human.name != name));
+
+        let result = pool.transaction_on_rollback(|_| Ok(1), |_| unreachable!());
+        assert_matches!(result, Ok(1));
+    }
+
+    #[test]
+    fn test_chunked_transaction_keeps_earlier_chunks() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+        let prefix = format!("chunked-{}", uuid::Uuid::new_v4());
+
+        let result = pool.chunked_transaction(0..5, 2, |conn, i| {
+            if i == 3 {
+                return Err(DbError::Diesel(diesel::result::Error::RollbackTransaction));
             }
-        })
+            conn.create_human(CreateHuman {
+                name: format!("{}-{}", prefix, i),
+                friend_ids: Vec::new(),
+            })?;
+            Ok(())
+        });
+
+        let persisted = pool
+            .with(|conn| conn.find_humans())
+            .unwrap()
+            .into_iter()
+            .filter(|human| human.name.starts_with(&prefix))
+            .collect::<Vec<_>>();
+        for human in &persisted {
+            pool.with(|conn| conn.delete_human(&human.id)).unwrap();
+        }
+
+        assert_matches!(result, Err(DbError::Diesel(_)));
+        let mut names = persisted
+            .into_iter()
+            .map(|human| human.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![format!("{}-0", prefix), format!("{}-1", prefix)]
+        );
     }
 }
@@ -0,0 +1,138 @@
+//! Read/write split pool.
+use crate::error::DbError;
+use crate::password::PasswordHasher;
+use crate::pool::{DbPool, PoolState};
+
+/// A pool that sends writes to a primary and reads to a replica.
+///
+/// `with` and `transaction` use the primary, `read` uses the replica.
+#[derive(Clone)]
+pub struct ReadWritePool<Pool> {
+    primary: Pool,
+    replica: Pool,
+}
+
+impl<Pool: DbPool> ReadWritePool<Pool> {
+    pub fn new(primary: Pool, replica: Pool) -> Self {
+        ReadWritePool { primary, replica }
+    }
+
+    pub fn primary(&self) -> &Pool {
+        &self.primary
+    }
+
+    pub fn replica(&self) -> &Pool {
+        &self.replica
+    }
+
+    /// Executes the given read-only function on the replica.
+    pub fn read<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Pool::Connection) -> Result<T, DbError>,
+    {
+        self.replica.with(f)
+    }
+}
+
+impl<Pool: DbPool> DbPool for ReadWritePool<Pool> {
+    type Connection = Pool::Connection;
+
+    fn with<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        self.primary.with(f)
+    }
+
+    fn transaction<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        self.primary.transaction(f)
+    }
+
+    fn state(&self) -> Option<PoolState> {
+        self.primary.state()
+    }
+
+    fn password_hasher(&self) -> &dyn PasswordHasher {
+        self.primary.password_hasher()
+    }
+
+    fn health_check(&self) -> Result<(), DbError> {
+        self.primary.health_check()?;
+        self.replica.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pg::PgPool;
+    use crate::test::TestPool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone)]
+    struct SpyPool {
+        pool: TestPool<PgPool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl SpyPool {
+        fn new() -> Self {
+            let database_url = dotenv::var("DATABASE_URL")
+                .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+            let pool = PgPool::new(&database_url).unwrap();
+            SpyPool {
+                pool: TestPool::with(pool),
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl DbPool for SpyPool {
+        type Connection = <TestPool<PgPool> as DbPool>::Connection;
+
+        fn with<F, T>(&self, f: F) -> Result<T, DbError>
+        where
+            F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+        {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.pool.with(f)
+        }
+    }
+
+    #[test]
+    fn test_read_uses_replica() {
+        let pool = ReadWritePool::new(SpyPool::new(), SpyPool::new());
+        let result = pool.read(|_| Ok(()));
+
+        assert!(result.is_ok());
+        assert_eq!(pool.primary().calls(), 0);
+        assert_eq!(pool.replica().calls(), 1);
+    }
+
+    #[test]
+    fn test_transaction_uses_primary() {
+        let pool = ReadWritePool::new(SpyPool::new(), SpyPool::new());
+        let result = pool.transaction(|_| Ok(()));
+
+        assert!(result.is_ok());
+        assert_eq!(pool.primary().calls(), 1);
+        assert_eq!(pool.replica().calls(), 0);
+    }
+
+    #[test]
+    fn test_health_check_checks_both() {
+        let pool = ReadWritePool::new(SpyPool::new(), SpyPool::new());
+
+        assert!(pool.health_check().is_ok());
+        assert_eq!(pool.primary().calls(), 1);
+        assert_eq!(pool.replica().calls(), 1);
+    }
+}
@@ -0,0 +1,146 @@
+//! Retrying with exponential backoff.
+use std::fmt::Display;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Retries a failing operation, doubling the delay before each retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// The number of retries after the first attempt.
+    pub retries: u32,
+    /// The delay before the first retry.
+    pub base_delay: Duration,
+    /// The time after which no more retries are made, counted from the
+    /// first attempt.
+    pub max_elapsed: Option<Duration>,
+}
+
+impl Backoff {
+    pub fn new(retries: u32, base_delay: Duration) -> Self {
+        Backoff {
+            retries,
+            base_delay,
+            max_elapsed: None,
+        }
+    }
+
+    /// Stops retrying when a retry would start after `max_elapsed`.
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    /// Calls `f` until it succeeds or the retries run out, returning the last
+    /// error then.
+    pub fn retry<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        E: Display,
+        F: FnMut() -> Result<T, E>,
+    {
+        self.retry_if(f, |_| true)
+    }
+
+    /// Like `retry`, but only retries the errors `retryable` accepts.
+    ///
+    /// The delays are slept on the calling thread.
+    pub fn retry_if<T, E, F, P>(&self, mut f: F, mut retryable: P) -> Result<T, E>
+    where
+        E: Display,
+        F: FnMut() -> Result<T, E>,
+        P: FnMut(&E) -> bool,
+    {
+        let start = Instant::now();
+        let mut delay = self.base_delay;
+        let mut attempt = 0;
+        loop {
+            match f() {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.retries && self.in_time(start, delay) && retryable(&e) => {
+                    attempt += 1;
+                    log::warn!("attempt {} failed, retrying in {:?}: {}", attempt, delay, e);
+                    thread::sleep(delay);
+                    delay *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn in_time(&self, start: Instant, delay: Duration) -> bool {
+        match self.max_elapsed {
+            Some(max_elapsed) => start.elapsed() + delay < max_elapsed,
+            None => true,
+        }
+    }
+}
+
+impl Default for Backoff {
+    /// No retries.
+    fn default() -> Self {
+        Backoff::new(0, Duration::from_millis(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn flaky(failures: u32, calls: &Cell<u32>) -> impl FnMut() -> Result<u32, String> + '_ {
+        move || {
+            calls.set(calls.get() + 1);
+            if calls.get() > failures {
+                Ok(calls.get())
+            } else {
+                Err("connection refused".to_owned())
+            }
+        }
+    }
+
+    #[test]
+    fn test_backoff_retry_should_succeed_within_retries() {
+        let calls = Cell::new(0);
+        let backoff = Backoff::new(3, Duration::from_millis(1));
+
+        assert_eq!(backoff.retry(flaky(2, &calls)), Ok(3));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_backoff_retry_should_give_up() {
+        let calls = Cell::new(0);
+        let backoff = Backoff::new(2, Duration::from_millis(1));
+
+        assert_matches!(backoff.retry(flaky(5, &calls)), Err(_));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_backoff_default_should_not_retry() {
+        let calls = Cell::new(0);
+
+        assert_matches!(Backoff::default().retry(flaky(1, &calls)), Err(_));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_backoff_retry_if_should_not_retry_rejected_errors() {
+        let calls = Cell::new(0);
+        let backoff = Backoff::new(3, Duration::from_millis(1));
+
+        let result = backoff.retry_if(flaky(2, &calls), |e| e != "connection refused");
+        assert_matches!(result, Err(_));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_backoff_retry_should_stop_after_max_elapsed() {
+        let calls = Cell::new(0);
+        let backoff =
+            Backoff::new(10, Duration::from_millis(20)).max_elapsed(Duration::from_millis(50));
+
+        assert_matches!(backoff.retry(flaky(10, &calls)), Err(_));
+        // retries after 0ms and 20ms, the next one would start at 60ms
+        assert_eq!(calls.get(), 2);
+    }
+}
@@ -0,0 +1,136 @@
+//! Slow query logging pool.
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+use crate::error::DbError;
+use crate::password::PasswordHasher;
+use crate::pool::{DbPool, PoolState};
+
+/// A pool that times the closures of its `with` and `transaction` calls and
+/// logs a warning with its label for those slower than the threshold.
+///
+/// Only the closure is timed, not waiting for a connection.
+#[derive(Clone)]
+pub struct SlowQueryPool<Pool> {
+    pool: Pool,
+    threshold: Duration,
+    label: Cow<'static, str>,
+}
+
+impl<Pool: DbPool> SlowQueryPool<Pool> {
+    /// Wraps `pool`, labeling its calls `db`.
+    pub fn new(pool: Pool, threshold: Duration) -> Self {
+        SlowQueryPool {
+            pool,
+            threshold,
+            label: Cow::Borrowed("db"),
+        }
+    }
+
+    /// A clone of the pool logging its slow calls with `label`, e.g. the
+    /// name of the endpoint making them.
+    pub fn with_label<L: Into<Cow<'static, str>>>(&self, label: L) -> Self
+    where
+        Pool: Clone,
+    {
+        SlowQueryPool {
+            pool: self.pool.clone(),
+            threshold: self.threshold,
+            label: label.into(),
+        }
+    }
+
+    fn timed<F, T>(&self, conn: &Pool::Connection, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Pool::Connection) -> Result<T, DbError>,
+    {
+        let start = Instant::now();
+        let result = f(conn);
+        let elapsed = start.elapsed();
+        if elapsed > self.threshold {
+            log::warn!(
+                "slow query [{}]: took {:?}, threshold {:?}",
+                self.label,
+                elapsed,
+                self.threshold
+            );
+        }
+        result
+    }
+}
+
+impl<Pool: DbPool> DbPool for SlowQueryPool<Pool> {
+    type Connection = Pool::Connection;
+
+    fn with<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        self.pool.with(|conn| self.timed(conn, f))
+    }
+
+    fn transaction<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        self.pool.transaction(|conn| self.timed(conn, f))
+    }
+
+    fn state(&self) -> Option<PoolState> {
+        self.pool.state()
+    }
+
+    fn password_hasher(&self) -> &dyn PasswordHasher {
+        self.pool.password_hasher()
+    }
+
+    fn health_check(&self) -> Result<(), DbError> {
+        self.pool.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::connection::SimpleConnection;
+    use log::Level;
+
+    use super::*;
+    use crate::pg::PgPool;
+    use crate::test::TestPool;
+    use crate::test_helpers::take_log_records;
+
+    fn init_pool(threshold: Duration) -> SlowQueryPool<TestPool<PgPool>> {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = TestPool::with(PgPool::new(&database_url).unwrap());
+        SlowQueryPool::new(pool, threshold)
+    }
+
+    #[test]
+    fn test_slow_query_pool_warns() {
+        let pool = init_pool(Duration::from_millis(20)).with_label("report");
+        take_log_records();
+
+        let result = pool.with(|conn| Ok(conn.batch_execute("SELECT pg_sleep(0.05)")?));
+        assert!(result.is_ok());
+        let result = pool.transaction(|conn| Ok(conn.batch_execute("SELECT pg_sleep(0.05)")?));
+        assert!(result.is_ok());
+
+        let records = take_log_records();
+        assert_eq!(records.len(), 2);
+        for (level, message) in records {
+            assert_eq!(level, Level::Warn);
+            assert!(message.starts_with("slow query [report]: took "));
+        }
+    }
+
+    #[test]
+    fn test_slow_query_pool_ignores_fast_queries() {
+        let pool = init_pool(Duration::from_secs(10));
+        take_log_records();
+
+        let result = pool.transaction(|conn| Ok(conn.batch_execute("SELECT 1")?));
+        assert!(result.is_ok());
+        assert!(take_log_records().is_empty());
+    }
+}
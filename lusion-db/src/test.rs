@@ -1,8 +1,12 @@
 //! Database test module.
-use diesel::connection::{Connection, TransactionManager};
+use std::sync::Mutex;
+
+use diesel::connection::{Connection, SimpleConnection, TransactionManager};
 
 use crate::error::DbError;
-use crate::pool::DbPool;
+use crate::password::PasswordHasher;
+use crate::pg::{PgConn, PgConnGuard, PgPool};
+use crate::pool::{nested_transaction, DbPool, IsolationLevel, PoolState};
 
 /// A test connection pool.
 #[derive(Clone)]
@@ -38,11 +42,84 @@ where
         })
     }
 
+    /// Runs `f` in a savepoint of the test transaction, so that its changes
+    /// are rolled back when it fails, like in a real transaction.
     fn transaction<F, T>(&self, f: F) -> Result<T, DbError>
     where
         F: FnOnce(&Self::Connection) -> Result<T, DbError>,
     {
-        self.with(f)
+        self.with(|conn| nested_transaction(conn, f))
+    }
+
+    /// Sets the isolation level of the test transaction, as it can't be set
+    /// in a savepoint.
+    fn transaction_with<F, T>(&self, level: IsolationLevel, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        self.with(|conn| {
+            conn.batch_execute(&format!(
+                "SET TRANSACTION ISOLATION LEVEL {}",
+                level.as_sql()
+            ))?;
+            nested_transaction(conn, f)
+        })
+    }
+
+    fn state(&self) -> Option<PoolState> {
+        self.0.state()
+    }
+
+    fn password_hasher(&self) -> &dyn PasswordHasher {
+        self.0.password_hasher()
+    }
+}
+
+/// A test pool of a single connection in a transaction that is never
+/// committed.
+///
+/// Unlike with `TestPool`, the changes of a call are seen by the later ones,
+/// e.g. the user created by one request of a test is found by the next one.
+/// They're rolled back when the pool is dropped, so other tests never see
+/// them.
+pub struct SharedTestPool {
+    pool: PgPool,
+    conn: Mutex<PgConnGuard>,
+}
+
+impl SharedTestPool {
+    pub fn with(pool: PgPool) -> Result<Self, DbError> {
+        let conn = pool.checkout()?;
+        conn.begin()?;
+        Ok(SharedTestPool {
+            pool,
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl DbPool for SharedTestPool {
+    type Connection = PgConn;
+
+    fn with<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        let conn = self.conn.lock().unwrap();
+        f(&conn)
+    }
+
+    /// Leaves the isolation level of the shared transaction as is, as it
+    /// can't be changed after its first query.
+    fn transaction_with<F, T>(&self, _level: IsolationLevel, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        self.transaction(f)
+    }
+
+    fn password_hasher(&self) -> &dyn PasswordHasher {
+        self.pool.password_hasher()
     }
 }
 
@@ -62,4 +139,36 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_shared_pool() {
+        use crate::users::{CreateUser, UserRepository};
+
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = PgPool::new(&database_url).unwrap();
+        let shared_pool = SharedTestPool::with(pool.clone()).unwrap();
+        let created = shared_pool.transaction(|conn| {
+            conn.create_user(
+                CreateUser {
+                    id: uuid::Uuid::new_v4(),
+                    username: "shareduser".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "shareduser".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &crate::password::BcryptHasher { cost: 4 },
+            )
+        });
+        assert!(created.is_ok());
+
+        let found = shared_pool.with(|conn| conn.find_user_by_username("shareduser"));
+        assert_matches!(found, Ok(Some(_)));
+        let found = pool.with(|conn| conn.find_user_by_username("shareduser"));
+        assert_matches!(found, Ok(None));
+
+        drop(shared_pool);
+        let found = pool.with(|conn| conn.find_user_by_username("shareduser"));
+        assert_matches!(found, Ok(None));
+    }
 }
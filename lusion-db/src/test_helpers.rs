@@ -1,5 +1,9 @@
 //! Test helpers.
+use std::cell::RefCell;
+use std::sync::Once;
+
 use diesel::connection::{Connection, TransactionManager};
+use log::{Level, LevelFilter, Log, Metadata, Record};
 
 use crate::error::DbError;
 use crate::pg::PgConn;
@@ -21,3 +25,107 @@ where
 
     result
 }
+
+thread_local! {
+    static LOG_RECORDS: RefCell<Vec<(Level, String)>> = RefCell::new(Vec::new());
+}
+
+/// Captures the log records of the current thread.
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LOG_RECORDS.with(|records| {
+            records
+                .borrow_mut()
+                .push((record.level(), record.args().to_string()))
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger;
+
+/// Takes the warnings and errors logged on the current thread since the
+/// last call.
+pub fn take_log_records() -> Vec<(Level, String)> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Warn);
+    });
+    LOG_RECORDS.with(|records| records.borrow_mut().drain(..).collect())
+}
+
+#[cfg(feature = "trace")]
+pub use self::recording::RecordingSubscriber;
+
+#[cfg(feature = "trace")]
+mod recording {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::{span, Event, Id, Metadata, Subscriber};
+
+    /// A recorded `tracing` span.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RecordedSpan {
+        pub id: u64,
+        pub name: &'static str,
+        pub parent: Option<u64>,
+    }
+
+    /// A `tracing` subscriber that records the spans created on the current
+    /// thread.
+    #[derive(Clone, Default)]
+    pub struct RecordingSubscriber {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+        stack: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl RecordingSubscriber {
+        pub fn spans(&self) -> Vec<RecordedSpan> {
+            self.spans.lock().unwrap().clone()
+        }
+    }
+
+    impl Subscriber for RecordingSubscriber {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &span::Attributes) -> Id {
+            let parent = if attrs.is_contextual() {
+                self.stack.lock().unwrap().last().cloned()
+            } else {
+                attrs.parent().map(Id::into_u64)
+            };
+            let mut spans = self.spans.lock().unwrap();
+            let id = spans.len() as u64 + 1;
+            spans.push(RecordedSpan {
+                id,
+                name: attrs.metadata().name(),
+                parent,
+            });
+            Id::from_u64(id)
+        }
+
+        fn record(&self, _: &Id, _: &span::Record) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event) {}
+
+        fn enter(&self, span: &Id) {
+            self.stack.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, _: &Id) {
+            self.stack.lock().unwrap().pop();
+        }
+    }
+}
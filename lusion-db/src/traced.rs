@@ -0,0 +1,89 @@
+//! Tracing instrumented pool.
+use tracing::{debug_span, Level};
+
+use crate::error::DbError;
+use crate::password::PasswordHasher;
+use crate::pool::{DbPool, PoolState};
+
+/// A pool that wraps its database calls in `tracing` spans.
+///
+/// `with` and `transaction` calls get a `db.with` or `db.transaction` span,
+/// nested in the span current at the call, e.g. the request span.
+#[derive(Clone)]
+pub struct TracedPool<Pool>(Pool);
+
+impl<Pool: DbPool> TracedPool<Pool> {
+    pub fn new(pool: Pool) -> Self {
+        TracedPool(pool)
+    }
+}
+
+impl<Pool: DbPool> DbPool for TracedPool<Pool> {
+    type Connection = Pool::Connection;
+
+    fn with<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        let span = debug_span!("db.with");
+        let _enter = span.enter();
+        let result = self.0.with(f);
+        if let Err(ref e) = result {
+            tracing::event!(Level::ERROR, error = %e, "database call failed");
+        }
+        result
+    }
+
+    fn transaction<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+    {
+        let span = debug_span!("db.transaction");
+        let _enter = span.enter();
+        let result = self.0.transaction(f);
+        if let Err(ref e) = result {
+            tracing::event!(Level::ERROR, error = %e, "database transaction failed");
+        }
+        result
+    }
+
+    fn state(&self) -> Option<PoolState> {
+        self.0.state()
+    }
+
+    fn password_hasher(&self) -> &dyn PasswordHasher {
+        self.0.password_hasher()
+    }
+
+    fn health_check(&self) -> Result<(), DbError> {
+        self.0.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pg::PgPool;
+    use crate::test::TestPool;
+    use crate::test_helpers::RecordingSubscriber;
+
+    #[test]
+    fn test_traced_pool_spans() {
+        let database_url = dotenv::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/lusion".to_owned());
+        let pool = TracedPool::new(TestPool::with(PgPool::new(&database_url).unwrap()));
+        let subscriber = RecordingSubscriber::default();
+
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            let span = tracing::info_span!("request");
+            let _enter = span.enter();
+            pool.transaction(|_| Ok(())).unwrap();
+        });
+
+        let spans = subscriber.spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].name, "request");
+        assert_eq!(spans[1].name, "db.transaction");
+        assert_eq!(spans[1].parent, Some(spans[0].id));
+    }
+}
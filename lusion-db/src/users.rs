@@ -1,9 +1,13 @@
 //! User repository
+use std::io::Read;
+
 use chrono::prelude::*;
 use diesel::prelude::*;
 use uuid::Uuid;
 
 use crate::error::DbError;
+use crate::page::{Page, Paged};
+use crate::password::PasswordHasher;
 use crate::pg::PgConn;
 use crate::schema::users;
 
@@ -18,16 +22,35 @@ pub struct User {
     pub avatar_url: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When the user was soft deleted, hiding it from the finders.
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateUser {
+    /// Generated by the caller, so that what's derived from it, like the
+    /// avatar, is known before the user is created. It's never deserialized,
+    /// so a request can't choose it; a deserialized user gets a random one.
+    #[serde(skip_deserializing, default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub username: String,
+    /// The plaintext password, hashed by `create_user`.
     pub password: String,
     pub nickname: String,
     pub avatar_url: String,
 }
 
+/// The order of `find_users_ordered`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserOrder {
+    /// Newest first.
+    CreatedAtDesc,
+    /// Oldest first.
+    CreatedAtAsc,
+    UsernameAsc,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateUserPassword {
     pub old_password: String,
@@ -37,66 +60,339 @@ pub struct UpdateUserPassword {
 pub trait UserRepository {
     fn find_user(&self, user_id: &Uuid) -> Result<Option<User>, DbError>;
 
+    /// Like `find_user`, but a missing user is a `DbError::NotFound`.
+    fn get_user(&self, user_id: &Uuid) -> Result<User, DbError> {
+        self.find_user(user_id)?.ok_or(DbError::NotFound)
+    }
+
+    fn find_user_by_username(&self, username: &str) -> Result<Option<User>, DbError>;
+
     fn find_users(&self) -> Result<Vec<User>, DbError>;
 
-    fn create_user(&self, input: CreateUser) -> Result<User, DbError>;
+    fn find_users_paged(&self, page: Page) -> Result<Paged<User>, DbError>;
+
+    /// Like `find_users`, in `order`, ties broken by id.
+    fn find_users_ordered(&self, order: UserOrder) -> Result<Vec<User>, DbError>;
+
+    /// Iterates over the users ordered by id, loading `STREAM_BATCH_SIZE` of
+    /// them at a time instead of the whole table.
+    ///
+    /// Diesel has no cursor support, so each batch is a keyset query for the
+    /// ids after the last one seen. Users created or deleted while iterating
+    /// may or may not be seen. The iteration ends after the first error.
+    fn stream_users<'a>(&'a self) -> Box<dyn Iterator<Item = Result<User, DbError>> + 'a>;
+
+    /// Creates a user, storing its password hashed with `hasher`.
+    fn create_user(&self, input: CreateUser, hasher: &dyn PasswordHasher) -> Result<User, DbError>;
+
+    /// Creates each user independently, so a failing item doesn't abort the others.
+    fn create_users_partial(
+        &self,
+        inputs: Vec<CreateUser>,
+        hasher: &dyn PasswordHasher,
+    ) -> Vec<Result<User, DbError>>;
 
-    fn update_user_password(&self, user_id: &Uuid, new_password: &str) -> Result<usize, DbError>;
+    /// Imports users, storing their passwords hashed with `hasher`, e.g.
+    /// from `read_csv_users`. Returns the number of users imported.
+    ///
+    /// Diesel has no `COPY ... FROM STDIN` support, so nothing is streamed:
+    /// the users are taken from `inputs` and inserted `IMPORT_BATCH_SIZE` at
+    /// a time. Only a batch is held here, but `inputs` may hold more, e.g.
+    /// the web import buffers the whole CSV and all its rows. The import
+    /// stops at the first error; run it inside a transaction to import all
+    /// of the users or none of them.
+    fn import_users(
+        &self,
+        inputs: &mut dyn Iterator<Item = Result<CreateUser, DbError>>,
+        hasher: &dyn PasswordHasher,
+    ) -> Result<usize, DbError>;
 
+    /// Stores the plaintext `new_password` hashed with `hasher`, unless the
+    /// user is soft deleted.
+    fn update_user_password(
+        &self,
+        user_id: &Uuid,
+        new_password: &str,
+        hasher: &dyn PasswordHasher,
+    ) -> Result<usize, DbError>;
+
+    /// Verifies the plaintext `password` of the user, false if there's no
+    /// such user. The password is re-hashed and stored when `hasher` would
+    /// hash it more strongly than the stored hash.
+    fn verify_password(
+        &self,
+        user_id: &Uuid,
+        password: &str,
+        hasher: &dyn PasswordHasher,
+    ) -> Result<bool, DbError>;
+
+    /// Soft deletes the user, hiding it from the finders until it's
+    /// restored. Its username stays taken.
     fn delete_user(&self, user_id: &Uuid) -> Result<usize, DbError>;
+
+    /// Restores a soft deleted user.
+    fn restore_user(&self, user_id: &Uuid) -> Result<usize, DbError>;
+}
+
+/// The number of rows `import_users` inserts per statement.
+pub const IMPORT_BATCH_SIZE: usize = 500;
+
+/// The number of rows `stream_users` loads per query.
+pub const STREAM_BATCH_SIZE: i64 = 500;
+
+/// Reads the users to import from CSV with a
+/// `username,password,nickname,avatar_url` header, where `password` is the
+/// plaintext password. Each user gets a new random id; an `id` column is
+/// ignored. A malformed row is a `DbError::Import`.
+pub fn read_csv_users<'a>(
+    reader: &'a mut dyn Read,
+) -> impl Iterator<Item = Result<CreateUser, DbError>> + 'a {
+    csv::Reader::from_reader(reader)
+        .into_deserialize()
+        .map(|record| record.map_err(|err| DbError::Import(err.to_string())))
+}
+
+/// The iterator of `stream_users`.
+struct UserStream<'a> {
+    conn: &'a PgConn,
+    batch_size: i64,
+    after: Option<Uuid>,
+    batch: std::vec::IntoIter<User>,
+    done: bool,
+}
+
+impl<'a> UserStream<'a> {
+    fn new(conn: &'a PgConn, batch_size: i64) -> Self {
+        UserStream {
+            conn,
+            batch_size,
+            after: None,
+            batch: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    fn load_batch(&self) -> Result<Vec<User>, DbError> {
+        let mut query = users::table
+            .filter(users::deleted_at.is_null())
+            .order(users::id)
+            .limit(self.batch_size)
+            .into_boxed();
+        if let Some(after) = self.after {
+            query = query.filter(users::id.gt(after));
+        }
+        Ok(query.load::<User>(self.conn)?)
+    }
+}
+
+impl<'a> Iterator for UserStream<'a> {
+    type Item = Result<User, DbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(user) = self.batch.next() {
+            self.after = Some(user.id);
+            return Some(Ok(user));
+        }
+        if self.done {
+            return None;
+        }
+
+        match self.load_batch() {
+            Ok(batch) => {
+                self.done = (batch.len() as i64) < self.batch_size;
+                self.batch = batch.into_iter();
+                let user = self.batch.next()?;
+                self.after = Some(user.id);
+                Some(Ok(user))
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+fn new_user(input: CreateUser, now: DateTime<Utc>) -> User {
+    User {
+        id: input.id,
+        username: input.username,
+        password: input.password,
+        nickname: input.nickname,
+        avatar_url: input.avatar_url,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    }
 }
 
 impl UserRepository for PgConn {
     fn find_user(&self, user_id: &Uuid) -> Result<Option<User>, DbError> {
         use crate::schema::users::dsl::*;
 
-        Ok(users.find(user_id).get_result::<User>(self).optional()?)
+        Ok(users
+            .find(user_id)
+            .filter(deleted_at.is_null())
+            .get_result::<User>(self)
+            .optional()?)
+    }
+
+    fn find_user_by_username(&self, username: &str) -> Result<Option<User>, DbError> {
+        Ok(users::table
+            .filter(users::username.eq(username))
+            .filter(users::deleted_at.is_null())
+            .get_result::<User>(self)
+            .optional()?)
     }
 
     fn find_users(&self) -> Result<Vec<User>, DbError> {
-        Ok(users::table.load::<User>(self)?)
+        Ok(users::table
+            .filter(users::deleted_at.is_null())
+            .load::<User>(self)?)
     }
 
-    fn create_user(&self, input: CreateUser) -> Result<User, DbError> {
-        let id = Uuid::new_v4();
-        let username = input.username;
-        let password = input.password;
-        let nickname = input.nickname;
-        let avatar_url = input.avatar_url;
-        let now = Utc::now();
+    fn find_users_paged(&self, page: Page) -> Result<Paged<User>, DbError> {
+        let total = users::table
+            .filter(users::deleted_at.is_null())
+            .count()
+            .get_result(self)?;
+        let items = users::table
+            .filter(users::deleted_at.is_null())
+            .order(users::id)
+            .limit(page.limit)
+            .offset(page.offset)
+            .load::<User>(self)?;
+
+        Ok(Paged { items, total })
+    }
+
+    fn find_users_ordered(&self, order: UserOrder) -> Result<Vec<User>, DbError> {
+        let query = users::table
+            .filter(users::deleted_at.is_null())
+            .into_boxed();
+        let query = match order {
+            UserOrder::CreatedAtDesc => query.order((users::created_at.desc(), users::id)),
+            UserOrder::CreatedAtAsc => query.order((users::created_at.asc(), users::id)),
+            UserOrder::UsernameAsc => query.order((users::username.asc(), users::id)),
+        };
+
+        Ok(query.load::<User>(self)?)
+    }
+
+    fn stream_users<'a>(&'a self) -> Box<dyn Iterator<Item = Result<User, DbError>> + 'a> {
+        Box::new(UserStream::new(self, STREAM_BATCH_SIZE))
+    }
 
+    fn create_user(&self, input: CreateUser, hasher: &dyn PasswordHasher) -> Result<User, DbError> {
+        let password = hasher.hash(&input.password)?;
         Ok(diesel::insert_into(users::table)
-            .values(User {
-                id,
-                username,
-                password,
-                nickname,
-                avatar_url,
-                created_at: now,
-                updated_at: now,
-            })
+            .values(new_user(CreateUser { password, ..input }, Utc::now()))
             .get_result(self)?)
     }
 
-    fn update_user_password(&self, user_id: &Uuid, new_password: &str) -> Result<usize, DbError> {
-        Ok(diesel::update(users::table.find(user_id))
+    fn create_users_partial(
+        &self,
+        inputs: Vec<CreateUser>,
+        hasher: &dyn PasswordHasher,
+    ) -> Vec<Result<User, DbError>> {
+        inputs
+            .into_iter()
+            .map(|input| self.transaction(|| self.create_user(input, hasher)))
+            .collect()
+    }
+
+    fn import_users(
+        &self,
+        inputs: &mut dyn Iterator<Item = Result<CreateUser, DbError>>,
+        hasher: &dyn PasswordHasher,
+    ) -> Result<usize, DbError> {
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        let mut imported = 0;
+        let now = Utc::now();
+        for input in inputs {
+            let input = input?;
+            let password = hasher.hash(&input.password)?;
+            batch.push(new_user(CreateUser { password, ..input }, now));
+            if batch.len() == IMPORT_BATCH_SIZE {
+                imported += diesel::insert_into(users::table)
+                    .values(&batch)
+                    .execute(self)?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            imported += diesel::insert_into(users::table)
+                .values(&batch)
+                .execute(self)?;
+        }
+
+        Ok(imported)
+    }
+
+    fn update_user_password(
+        &self,
+        user_id: &Uuid,
+        new_password: &str,
+        hasher: &dyn PasswordHasher,
+    ) -> Result<usize, DbError> {
+        let password = hasher.hash(new_password)?;
+        let user = users::table
+            .find(user_id)
+            .filter(users::deleted_at.is_null());
+        Ok(diesel::update(user)
             .set((
-                users::password.eq(&new_password),
+                users::password.eq(&password),
                 users::updated_at.eq(&Utc::now()),
             ))
             .execute(self)?)
     }
 
+    fn verify_password(
+        &self,
+        user_id: &Uuid,
+        password: &str,
+        hasher: &dyn PasswordHasher,
+    ) -> Result<bool, DbError> {
+        let user = match self.find_user(user_id)? {
+            Some(user) => user,
+            None => return Ok(false),
+        };
+        if !hasher.verify(password, &user.password)? {
+            return Ok(false);
+        }
+        if hasher.needs_rehash(&user.password) {
+            self.update_user_password(user_id, password, hasher)?;
+        }
+        Ok(true)
+    }
+
     fn delete_user(&self, user_id: &Uuid) -> Result<usize, DbError> {
-        Ok(diesel::delete(users::table.find(user_id)).execute(self)?)
+        let user = users::table
+            .find(user_id)
+            .filter(users::deleted_at.is_null());
+        Ok(diesel::update(user)
+            .set(users::deleted_at.eq(Some(Utc::now())))
+            .execute(self)?)
+    }
+
+    fn restore_user(&self, user_id: &Uuid) -> Result<usize, DbError> {
+        let user = users::table
+            .find(user_id)
+            .filter(users::deleted_at.is_not_null());
+        Ok(diesel::update(user)
+            .set(users::deleted_at.eq(None::<DateTime<Utc>>))
+            .execute(self)?)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::password::BcryptHasher;
     use crate::test_helpers::*;
 
+    const HASHER: BcryptHasher = BcryptHasher { cost: 4 };
+
     #[test]
     fn test_find_users_should_ok() {
         let result = with_transaction(|conn| conn.find_users());
@@ -104,6 +400,122 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_find_users_paged_should_ok() {
+        let result = with_transaction(|conn| {
+            for i in 0..5 {
+                conn.create_user(
+                    CreateUser {
+                        id: Uuid::new_v4(),
+                        username: format!("user{}", i),
+                        password: "1234".to_owned(),
+                        nickname: format!("user{}", i),
+                        avatar_url: "empty.png".to_owned(),
+                    },
+                    &HASHER,
+                )?;
+            }
+
+            let all = conn.find_users_paged(Page::new(5, 0))?;
+            let page = conn.find_users_paged(Page::new(2, 2))?;
+            let last = conn.find_users_paged(Page::new(2, 4))?;
+
+            Ok((all, page, last))
+        });
+
+        assert_matches!(result, Ok((all, page, last)) => {
+            assert_eq!(all.total, 5);
+            assert_eq!(page.total, 5);
+            assert_eq!(page.items[..], all.items[2..4]);
+            assert_eq!(last.items[..], all.items[4..]);
+        });
+    }
+
+    #[test]
+    fn test_find_users_ordered_should_ok() {
+        use chrono::Duration;
+
+        let result = with_transaction(|conn| {
+            let now = Utc::now();
+            let users = [("carol", 2), ("alice", 1), ("bob", 3)]
+                .iter()
+                .map(|&(name, minutes_ago)| {
+                    let input = CreateUser {
+                        id: Uuid::new_v4(),
+                        username: name.to_owned(),
+                        password: "hash".to_owned(),
+                        nickname: name.to_owned(),
+                        avatar_url: "empty.png".to_owned(),
+                    };
+                    new_user(input, now - Duration::minutes(minutes_ago))
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(users::table)
+                .values(&users)
+                .execute(conn)?;
+
+            let mut orders = Vec::new();
+            for &order in &[
+                UserOrder::CreatedAtDesc,
+                UserOrder::CreatedAtAsc,
+                UserOrder::UsernameAsc,
+            ] {
+                let names = conn
+                    .find_users_ordered(order)?
+                    .into_iter()
+                    .map(|user| user.username)
+                    .collect::<Vec<_>>();
+                orders.push(names);
+            }
+
+            Ok(orders)
+        });
+
+        assert_matches!(result, Ok(orders) => {
+            assert_eq!(orders[0], vec!["alice", "carol", "bob"]);
+            assert_eq!(orders[1], vec!["bob", "carol", "alice"]);
+            assert_eq!(orders[2], vec!["alice", "bob", "carol"]);
+        });
+    }
+
+    #[test]
+    fn test_stream_users_should_ok() {
+        let result = with_transaction(|conn| {
+            for i in 0..5 {
+                conn.create_user(
+                    CreateUser {
+                        id: Uuid::new_v4(),
+                        username: format!("user{}", i),
+                        password: "1234".to_owned(),
+                        nickname: format!("user{}", i),
+                        avatar_url: "empty.png".to_owned(),
+                    },
+                    &HASHER,
+                )?;
+            }
+            let deleted = conn.find_user_by_username("user0")?.unwrap();
+            conn.delete_user(&deleted.id)?;
+
+            let mut count = 0;
+            for user in conn.stream_users() {
+                user?;
+                count += 1;
+            }
+            let mut ids = Vec::new();
+            for user in UserStream::new(conn, 2) {
+                ids.push(user?.id);
+            }
+            let paged = conn.find_users_paged(Page::new(10, 0))?;
+
+            Ok((count, ids, paged))
+        });
+
+        assert_matches!(result, Ok((count, ids, paged)) => {
+            assert_eq!(count, 4);
+            assert_eq!(ids, paged.items.iter().map(|user| user.id).collect::<Vec<_>>());
+        });
+    }
+
     #[test]
     fn test_find_user_should_ok() {
         let result = with_transaction(|conn| conn.find_user(&Uuid::new_v4()));
@@ -111,35 +523,358 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_get_user_should_ok() {
+        let result = with_transaction(|conn| {
+            let user = conn.create_user(
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "testuser".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "testname".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &HASHER,
+            )?;
+            let found = conn.get_user(&user.id)?;
+            let missing = conn.get_user(&Uuid::new_v4());
+
+            Ok((user, found, missing))
+        });
+
+        assert_matches!(result, Ok((user, found, missing)) => {
+            assert_eq!(found, user);
+            assert_matches!(missing, Err(DbError::NotFound));
+        });
+    }
+
+    #[test]
+    fn test_find_user_by_username_should_ok() {
+        let result = with_transaction(|conn| {
+            let user = conn.create_user(
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "admin".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "admin".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &HASHER,
+            )?;
+            let found = conn.find_user_by_username("admin")?;
+            let missing = conn.find_user_by_username("nobody")?;
+
+            Ok((user, found, missing))
+        });
+
+        assert_matches!(result, Ok((user, found, missing)) => {
+            assert_eq!(found, Some(user));
+            assert_eq!(missing, None);
+        });
+    }
+
     #[test]
     fn test_create_user_should_ok() {
+        let id = Uuid::new_v4();
         let result = with_transaction(|conn| {
-            conn.create_user(CreateUser {
-                username: "admin".to_owned(),
-                password: "1234".to_owned(),
-                nickname: "admin".to_owned(),
-                avatar_url: "empty.png".to_owned(),
-            })
+            conn.create_user(
+                CreateUser {
+                    id,
+                    username: "admin".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "admin".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &HASHER,
+            )
         });
 
         assert_matches!(result, Ok(user) => {
+            assert_eq!(user.id, id);
             assert_eq!(user.username, "admin");
             assert_eq!(user.nickname, "admin");
             assert_eq!(user.avatar_url, "empty.png");
         });
     }
 
+    #[test]
+    fn test_create_user_duplicate_username_should_conflict() {
+        let result = with_transaction(|conn| {
+            for nickname in &["admin", "another admin"] {
+                conn.create_user(
+                    CreateUser {
+                        id: Uuid::new_v4(),
+                        username: "admin".to_owned(),
+                        password: "1234".to_owned(),
+                        nickname: nickname.to_string(),
+                        avatar_url: "empty.png".to_owned(),
+                    },
+                    &HASHER,
+                )?;
+            }
+            Ok(())
+        });
+
+        assert_matches!(result, Err(DbError::Conflict(message)) => {
+            assert!(message.contains("duplicate key"), "{}", message);
+        });
+    }
+
+    #[test]
+    fn test_create_user_should_hash_password() {
+        let result = with_transaction(|conn| {
+            let user = conn.create_user(
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "admin".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "admin".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &HASHER,
+            )?;
+            let verified = conn.verify_password(&user.id, "1234", &HASHER)?;
+            let wrong = conn.verify_password(&user.id, "4321", &HASHER)?;
+            let missing = conn.verify_password(&Uuid::new_v4(), "1234", &HASHER)?;
+
+            conn.update_user_password(&user.id, "4321", &HASHER)?;
+            let updated = conn.find_user(&user.id)?.unwrap();
+            let new_verified = conn.verify_password(&user.id, "4321", &HASHER)?;
+            let old_verified = conn.verify_password(&user.id, "1234", &HASHER)?;
+
+            Ok((
+                user,
+                verified,
+                wrong,
+                missing,
+                updated,
+                new_verified,
+                old_verified,
+            ))
+        });
+
+        assert_matches!(
+            result,
+            Ok((user, verified, wrong, missing, updated, new_verified, old_verified)) => {
+                assert!(user.password.starts_with("$2"));
+                assert!(HASHER.verify("1234", &user.password).unwrap());
+                assert!(verified);
+                assert!(!wrong);
+                assert!(!missing);
+                assert_ne!(updated.password, "4321");
+                assert!(new_verified);
+                assert!(!old_verified);
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_password_should_upgrade_hash() {
+        let result = with_transaction(|conn| {
+            let user = conn.create_user(
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "admin".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "admin".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &HASHER,
+            )?;
+            let hasher = BcryptHasher { cost: 6 };
+
+            let wrong = conn.verify_password(&user.id, "4321", &hasher)?;
+            let unchanged = conn.find_user(&user.id)?.unwrap();
+            let verified = conn.verify_password(&user.id, "1234", &hasher)?;
+            let upgraded = conn.find_user(&user.id)?.unwrap();
+
+            Ok((wrong, unchanged, verified, upgraded))
+        });
+
+        assert_matches!(result, Ok((wrong, unchanged, verified, upgraded)) => {
+            assert!(!wrong);
+            assert_eq!(unchanged.password.split('$').nth(2), Some("04"));
+            assert!(verified);
+            assert_eq!(upgraded.password.split('$').nth(2), Some("06"));
+        });
+    }
+
+    #[test]
+    fn test_create_users_partial_should_ok() {
+        let result = with_transaction(|conn| {
+            let inputs = vec![
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "alice".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "alice".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "alice".to_owned(),
+                    password: "4321".to_owned(),
+                    nickname: "another alice".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+            ];
+            let results = conn.create_users_partial(inputs, &HASHER);
+            let users = conn.find_users()?;
+
+            Ok((results, users))
+        });
+
+        assert_matches!(result, Ok((results, users)) => {
+            assert_eq!(results.len(), 2);
+            assert_matches!(results[0], Ok(ref user) => {
+                assert_eq!(user.nickname, "alice");
+            });
+            assert_matches!(results[1], Err(DbError::Conflict(_)));
+            assert_eq!(users.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_import_users_should_ok() {
+        let csv = "username,password,nickname,avatar_url\n\
+                   alice,1234,alice,empty.png\n\
+                   bob,4321,\"Bob, Jr.\",empty.png\n";
+        let result = with_transaction(|conn| {
+            let imported = conn.import_users(&mut read_csv_users(&mut csv.as_bytes()), &HASHER)?;
+            let users = conn.find_users()?;
+
+            Ok((imported, users))
+        });
+
+        assert_matches!(result, Ok((imported, users)) => {
+            assert_eq!(imported, 2);
+            assert_eq!(users.len(), 2);
+            let alice = users.iter().find(|user| user.username == "alice").unwrap();
+            assert_ne!(alice.password, "1234");
+            assert!(HASHER.verify("1234", &alice.password).unwrap());
+            assert!(users.iter().any(|user| user.nickname == "Bob, Jr."));
+        });
+    }
+
+    #[test]
+    fn test_read_csv_users_ignores_id() {
+        let id = Uuid::new_v4();
+        let csv = format!(
+            "id,username,password,nickname,avatar_url\n\
+             {0},alice,1234,alice,empty.png\n\
+             {0},bob,4321,bob,empty.png\n",
+            id
+        );
+        let users = read_csv_users(&mut csv.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(users.len(), 2);
+        assert_ne!(users[0].id, id);
+        assert_ne!(users[1].id, id);
+        assert_ne!(users[0].id, users[1].id);
+    }
+
+    #[test]
+    fn test_import_users_in_batches() {
+        let mut csv = "username,password,nickname,avatar_url\n".to_owned();
+        for i in 0..IMPORT_BATCH_SIZE + 1 {
+            csv.push_str(&format!("user{0},1234,user{0},empty.png\n", i));
+        }
+        let result = with_transaction(|conn| {
+            let imported = conn.import_users(&mut read_csv_users(&mut csv.as_bytes()), &HASHER)?;
+            let total = conn.find_users_paged(Page::new(1, 0))?.total;
+
+            Ok((imported, total))
+        });
+
+        assert_matches!(result, Ok((imported, total)) => {
+            assert_eq!(imported, IMPORT_BATCH_SIZE + 1);
+            assert_eq!(total, IMPORT_BATCH_SIZE as i64 + 1);
+        });
+    }
+
+    #[test]
+    fn test_import_users_malformed_csv() {
+        let csv = "username,password\nalice,1234\n";
+        let result = with_transaction(|conn| {
+            conn.import_users(&mut read_csv_users(&mut csv.as_bytes()), &HASHER)
+        });
+
+        assert_matches!(result, Err(DbError::Import(_)));
+    }
+
     #[test]
     fn test_update_user_password_should_ok() {
-        let result = with_transaction(|conn| conn.update_user_password(&Uuid::new_v4(), "4321"));
+        let result =
+            with_transaction(|conn| conn.update_user_password(&Uuid::new_v4(), "4321", &HASHER));
 
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_update_soft_deleted_user_password_should_update_none() {
+        let result = with_transaction(|conn| {
+            let user = conn.create_user(
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "deleted".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "deleted".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &HASHER,
+            )?;
+            conn.delete_user(&user.id)?;
+
+            conn.update_user_password(&user.id, "4321", &HASHER)
+        });
+
+        assert_matches!(result, Ok(0));
+    }
+
     #[test]
     fn test_delete_user_should_ok() {
         let result = with_transaction(|conn| conn.delete_user(&Uuid::new_v4()));
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_soft_deleted_user_should_be_hidden_and_restorable() {
+        let result = with_transaction(|conn| {
+            let user = conn.create_user(
+                CreateUser {
+                    id: Uuid::new_v4(),
+                    username: "admin".to_owned(),
+                    password: "1234".to_owned(),
+                    nickname: "admin".to_owned(),
+                    avatar_url: "empty.png".to_owned(),
+                },
+                &HASHER,
+            )?;
+
+            let deleted = conn.delete_user(&user.id)?;
+            let deleted_again = conn.delete_user(&user.id)?;
+            let hidden = (
+                conn.find_user(&user.id)?,
+                conn.find_user_by_username("admin")?,
+                conn.find_users()?.len(),
+                conn.find_users_paged(Page::new(10, 0))?.total,
+            );
+
+            let restored = conn.restore_user(&user.id)?;
+            let found = conn.find_user(&user.id)?;
+
+            Ok((user, deleted, deleted_again, hidden, restored, found))
+        });
+
+        assert_matches!(result, Ok((user, deleted, deleted_again, hidden, restored, found)) => {
+            assert_eq!(deleted, 1);
+            assert_eq!(deleted_again, 0);
+            assert_eq!(hidden, (None, None, 0, 0));
+            assert_eq!(restored, 1);
+            assert_eq!(found, Some(user));
+        });
+    }
 }
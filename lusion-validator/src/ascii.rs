@@ -0,0 +1,66 @@
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates that a string only contains ASCII characters.
+pub struct Ascii;
+
+/// Validates that a string only contains printable ASCII characters.
+pub struct AsciiPrintable;
+
+impl<T> Validator<T> for Ascii
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        if value.as_ref().is_ascii() {
+            None
+        } else {
+            Some(ValidationError::new("ascii"))
+        }
+    }
+}
+
+impl<T> Validator<T> for AsciiPrintable
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        let printable = value
+            .as_ref()
+            .bytes()
+            .all(|b| b == b' ' || b.is_ascii_graphic());
+
+        if printable {
+            None
+        } else {
+            Some(ValidationError::new("ascii_printable"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_validator() {
+        assert_matches!(Ascii.validate(&"my-subdomain 01"), None);
+        assert_matches!(Ascii.validate(&"tab\tted"), None);
+
+        let error = Ascii.validate(&"café".to_owned());
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::new("ascii"));
+        });
+    }
+
+    #[test]
+    fn test_ascii_printable_validator() {
+        assert_matches!(AsciiPrintable.validate(&"my-subdomain 01"), None);
+
+        for value in &["tab\tted", "bell\u{7}", "café"] {
+            let error = AsciiPrintable.validate(value);
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::new("ascii_printable"));
+            });
+        }
+    }
+}
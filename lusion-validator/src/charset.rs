@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates that a string only contains characters of `allowed`, e.g.
+/// `"abcdefghijklmnopqrstuvwxyz0123456789-"` for slugs.
+///
+/// Fails with `charset` and the position of the first offending character,
+/// counted in characters from 0. The empty string passes unless
+/// `allow_empty(false)` is set, in which case it fails at position 0.
+#[allow(non_snake_case)]
+pub fn Charset(allowed: &str) -> CharsetValidator {
+    CharsetValidator {
+        allowed: Arc::new(allowed.chars().collect()),
+        allow_empty: true,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CharsetValidator {
+    allowed: Arc<BTreeSet<char>>,
+    allow_empty: bool,
+}
+
+impl CharsetValidator {
+    /// Whether the empty string passes, `true` by default.
+    pub fn allow_empty(mut self, allow_empty: bool) -> Self {
+        self.allow_empty = allow_empty;
+        self
+    }
+}
+
+impl<T> Validator<T> for CharsetValidator
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        let value = value.as_ref();
+        if value.is_empty() {
+            return if self.allow_empty {
+                None
+            } else {
+                Some(ValidationError::with_params("charset", &[0]))
+            };
+        }
+
+        value
+            .chars()
+            .position(|c| !self.allowed.contains(&c))
+            .map(|position| ValidationError::with_params("charset", &[position]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLUG: &str = "abcdefghijklmnopqrstuvwxyz0123456789-";
+
+    #[test]
+    fn test_charset_validator() {
+        let validator = Charset(SLUG);
+        assert_matches!(validator.validate(&"my-slug-01"), None);
+        assert_matches!(validator.validate(&"my-slug-01".to_owned()), None);
+
+        let error = validator.validate(&"my-Slug");
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("charset", &[3]));
+        });
+        let error = validator.validate(&"café slug");
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("charset", &[3]));
+        });
+        let error = validator.validate(&"my slug");
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("charset", &[2]));
+        });
+    }
+
+    #[test]
+    fn test_charset_validator_empty() {
+        assert_matches!(Charset(SLUG).validate(&""), None);
+
+        let error = Charset(SLUG).allow_empty(false).validate(&"");
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("charset", &[0]));
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use super::{PlainValue, ValidationError, Validator};
+
+/// Passes if both validators pass, otherwise yields the first error.
+#[allow(non_snake_case)]
+pub fn And<A, B>(a: A, b: B) -> AndValidator<A, B> {
+    AndValidator(a, b)
+}
+
+/// Passes if either validator passes, otherwise yields an `or` error with
+/// both errors as params.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{Length, Or, Phone, Validator};
+///
+/// let validator = Or(Phone, Length(0, 0));
+///
+/// assert!(validator.validate(&"+14155550123").is_none());
+/// assert!(validator.validate(&"").is_none());
+/// assert!(validator.validate(&"415-555-0123").is_some());
+/// ```
+#[allow(non_snake_case)]
+pub fn Or<A, B>(a: A, b: B) -> OrValidator<A, B> {
+    OrValidator(a, b)
+}
+
+pub struct AndValidator<A, B>(A, B);
+
+pub struct OrValidator<A, B>(A, B);
+
+impl<T, A, B> Validator<T> for AndValidator<A, B>
+where
+    T: PlainValue,
+    A: Validator<T>,
+    B: Validator<T>,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        self.0.validate(value).or_else(|| self.1.validate(value))
+    }
+}
+
+impl<T, A, B> Validator<T> for OrValidator<A, B>
+where
+    T: PlainValue,
+    A: Validator<T>,
+    B: Validator<T>,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        let a = self.0.validate(value)?;
+        let b = self.1.validate(value)?;
+        Some(ValidationError::with_params("or", &[a, b]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate, Length};
+
+    struct Digits;
+
+    impl Validator<String> for Digits {
+        fn validate(&self, value: &String) -> Option<ValidationError> {
+            if value.bytes().all(|b| b.is_ascii_digit()) {
+                None
+            } else {
+                Some(ValidationError::new("digits"))
+            }
+        }
+    }
+
+    #[test]
+    fn test_and_validator() {
+        let validator = And(Length(1, 4), Digits);
+        assert_matches!(validator.validate(&"1234".to_owned()), None);
+
+        let error = validator.validate(&"12345".to_owned());
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("length", &[1, 4]));
+        });
+
+        let error = validator.validate(&"12a".to_owned());
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::new("digits"));
+        });
+    }
+
+    #[test]
+    fn test_or_validator() {
+        let validator = Or(Length(1, 4), Digits);
+        assert_matches!(validator.validate(&"abc".to_owned()), None);
+        assert_matches!(validator.validate(&"123456".to_owned()), None);
+
+        let error = validator.validate(&"abcdef".to_owned());
+        assert_matches!(error, Some(err) => {
+            assert_eq!(
+                err,
+                ValidationError::with_params(
+                    "or",
+                    &[
+                        ValidationError::with_params("length", &[1, 4]),
+                        ValidationError::new("digits"),
+                    ]
+                )
+            );
+            assert_eq!(
+                serde_json::to_string(&err).unwrap(),
+                r#"{"code":"or","params":[{"code":"length","params":[1,4]},{"code":"digits","params":[]}]}"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_combinators_nest_in_validate_macro() {
+        struct User {
+            code: Option<String>,
+        }
+
+        let user = User {
+            code: Some("abcdef".to_owned()),
+        };
+        let errors = validate!(user, {
+            code: [Or(And(Length(1, 4), Digits), Length(6, 6))],
+        });
+        assert!(errors.is_empty());
+
+        let user = User {
+            code: Some("12345".to_owned()),
+        };
+        let errors = validate!(user, {
+            code: [Or(And(Length(1, 4), Digits), Length(6, 6))],
+        });
+        assert_eq!(
+            errors["code"][0],
+            ValidationError::with_params(
+                "or",
+                &[
+                    ValidationError::with_params("length", &[1, 4]),
+                    ValidationError::with_params("length", &[6, 6]),
+                ]
+            )
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use super::{ValidationError, ValidationErrors, Validator};
+
+/// Validates every item of a `Vec` with the inner validator.
+///
+/// In `validate!` the error of an item is keyed by its index, e.g. `tags[2]`,
+/// with its params untouched. On its own, the
+/// validator yields an `each` error with the indexes of the failing items.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{validate, Each, MaxLength, ValidationError};
+///
+/// struct Post {
+///     tags: Vec<String>,
+/// }
+///
+/// let post = Post {
+///     tags: vec!["rust".to_owned(), "a-very-long-tag".to_owned()],
+/// };
+///
+/// let errors = validate!(post, {
+///     tags: [Each(MaxLength(8))],
+/// });
+///
+/// assert_eq!(
+///     errors["tags[1]"],
+///     vec![ValidationError::with_params("max_length", &[8])]
+/// );
+/// ```
+#[allow(non_snake_case)]
+pub fn Each<V>(validator: V) -> EachValidator<V> {
+    EachValidator(validator)
+}
+
+pub struct EachValidator<V>(V);
+
+impl<V> EachValidator<V> {
+    fn errors<'a, T>(
+        &'a self,
+        items: &'a [T],
+    ) -> impl Iterator<Item = (usize, ValidationError)> + 'a
+    where
+        V: Validator<T>,
+    {
+        items
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, item)| self.0.validate(item).map(|error| (index, error)))
+    }
+}
+
+impl<T, V> Validator<Vec<T>> for EachValidator<V>
+where
+    V: Validator<T>,
+{
+    fn validate(&self, items: &Vec<T>) -> Option<ValidationError> {
+        let indexes = self
+            .errors(items)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if indexes.is_empty() {
+            None
+        } else {
+            Some(ValidationError::with_params("each", &indexes))
+        }
+    }
+
+    fn validate_field(&self, name: &str, items: &Vec<T>, errors: &mut ValidationErrors) {
+        for (index, error) in self.errors(items) {
+            errors
+                .entry(format!("{}[{}]", name, index))
+                .or_insert_with(Vec::new)
+                .push(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate, Length, MaxLength};
+
+    #[test]
+    fn test_each_validator() {
+        let tags = vec![
+            "rust".to_owned(),
+            "".to_owned(),
+            "web".to_owned(),
+            "".to_owned(),
+        ];
+
+        let error = Each(Length(1, 10)).validate(&tags);
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("each", &[1, 3]));
+        });
+
+        let tags = vec!["rust".to_owned(), "web".to_owned()];
+        assert_matches!(Each(Length(1, 10)).validate(&tags), None);
+    }
+
+    #[test]
+    fn test_each_validator_in_validate_macro() {
+        struct Post {
+            tags: Vec<String>,
+            labels: Option<Vec<&'static str>>,
+        }
+
+        let post = Post {
+            tags: vec![
+                "rust".to_owned(),
+                "".to_owned(),
+                "a-very-long-tag".to_owned(),
+            ],
+            labels: Some(vec!["ok", "not-ok"]),
+        };
+
+        let errors = validate!(post, {
+            tags: [MaxLength(2), Each(Length(1, 10))],
+            labels: [Each(MaxLength(2))],
+        });
+
+        let fields = errors.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(fields, vec!["tags", "tags[1]", "tags[2]", "labels[1]"]);
+        assert_eq!(
+            errors["tags[2]"],
+            vec![ValidationError::with_params("length", &[1, 10])]
+        );
+        assert_eq!(
+            errors["labels[1]"],
+            vec![ValidationError::with_params("max_length", &[2])]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates that a string parses as `E` with its `FromStr` impl.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::str::FromStr;
+///
+/// use lusion_validator::{EnumValue, Validator};
+///
+/// enum Role {
+///     Admin,
+///     User,
+/// }
+///
+/// impl FromStr for Role {
+///     type Err = ();
+///
+///     fn from_str(s: &str) -> Result<Self, ()> {
+///         match s {
+///             "admin" => Ok(Role::Admin),
+///             "user" => Ok(Role::User),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// assert!(EnumValue::<Role>().validate(&"admin").is_none());
+/// assert!(EnumValue::<Role>().validate(&"root").is_some());
+/// ```
+#[allow(non_snake_case)]
+pub fn EnumValue<E: FromStr>() -> EnumValueValidator<E> {
+    EnumValueValidator(PhantomData)
+}
+
+pub struct EnumValueValidator<E>(PhantomData<fn() -> E>);
+
+impl<T, E> Validator<T> for EnumValueValidator<E>
+where
+    T: AsRef<str> + PlainValue,
+    E: FromStr,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        match E::from_str(value.as_ref()) {
+            Ok(_) => None,
+            Err(_) => Some(ValidationError::new("invalid_enum")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Role {
+        Admin,
+        User,
+    }
+
+    impl FromStr for Role {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, String> {
+            match s {
+                "admin" => Ok(Role::Admin),
+                "user" => Ok(Role::User),
+                _ => Err(format!("unknown role: {}", s)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_enum_value_validator() {
+        for value in &["admin", "user"] {
+            assert_matches!(EnumValue::<Role>().validate(value), None);
+        }
+
+        for value in &["", "Admin", "root"] {
+            let error = EnumValue::<Role>().validate(&value.to_string());
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::new("invalid_enum"));
+            });
+        }
+    }
+}
@@ -1,9 +1,25 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
 
-pub type ValidationErrors = HashMap<&'static str, Vec<ValidationError>>;
+use indexmap::IndexMap;
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Validation errors keyed by field, in the order the fields were validated.
+///
+/// The errors of nested fields are keyed by their dotted path, e.g.
+/// `address.zip`.
+pub type ValidationErrors = IndexMap<String, Vec<ValidationError>>;
+
+/// Renders `errors` as a JSON object of the fields and their errors, e.g.
+/// `{"username": [{"code": "length", "params": [1, 20]}]}`.
+///
+/// The fields keep the order they were validated in, and no errors render as
+/// `{}`. The JSON deserializes back into `ValidationErrors`.
+pub fn to_json(errors: &ValidationErrors) -> serde_json::Value {
+    serde_json::to_value(errors).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ValidationError {
     code: Cow<'static, str>,
     params: Vec<serde_json::Value>,
@@ -17,6 +33,14 @@ impl ValidationError {
         }
     }
 
+    /// Creates an error with an owned or dynamically built code.
+    pub fn with_code<S: Into<Cow<'static, str>>>(code: S) -> Self {
+        ValidationError {
+            code: code.into(),
+            params: Vec::new(),
+        }
+    }
+
     pub fn with_params<P: serde::Serialize>(code: &'static str, params: &[P]) -> Self {
         ValidationError {
             code: Cow::from(code),
@@ -31,4 +55,147 @@ impl ValidationError {
         self.params.push(serde_json::to_value(param).unwrap());
         self
     }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn params(&self) -> &[serde_json::Value] {
+        &self.params
+    }
+}
+
+/// Renders the code followed by the params, e.g. `length(1, 20)`.
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.code)?;
+        if !self.params.is_empty() {
+            f.write_str("(")?;
+            for (i, param) in self.params.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", param)?;
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+/// `ValidationErrors` as an `Error`, rendering each field and its errors,
+/// e.g. `username: length(1, 20); password: required`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport(pub ValidationErrors);
+
+impl From<ValidationErrors> for ValidationReport {
+    fn from(errors: ValidationErrors) -> Self {
+        ValidationReport(errors)
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (field, errors)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}: ", field)?;
+            for (j, error) in errors.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationReport {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_error_with_owned_code() {
+        let code = format!("max_{}", "items");
+        let error = ValidationError::with_code(code);
+
+        assert_eq!(error, ValidationError::new("max_items"));
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"code":"max_items","params":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_validation_errors_to_json() {
+        let mut errors = ValidationErrors::new();
+        assert_eq!(to_json(&errors).to_string(), "{}");
+
+        errors.insert(
+            "username".to_owned(),
+            vec![ValidationError::with_params("length", &[1, 20])],
+        );
+        errors.insert(
+            "address.zip".to_owned(),
+            vec![ValidationError::new("required")],
+        );
+        let json = to_json(&errors);
+        let fields = json.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(fields, ["username", "address.zip"]);
+        assert_eq!(
+            json.to_string(),
+            r#"{"username":[{"code":"length","params":[1,20]}],"address.zip":[{"code":"required","params":[]}]}"#
+        );
+
+        let parsed: ValidationErrors = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, errors);
+    }
+
+    #[test]
+    fn test_validation_error_display() {
+        let error = ValidationError::with_params("length", &[1, 20]);
+        assert_eq!(error.to_string(), "length(1, 20)");
+        assert_eq!(ValidationError::new("required").to_string(), "required");
+        assert_eq!(
+            ValidationError::with_params("pattern", &["^a$"]).to_string(),
+            r#"pattern("^a$")"#
+        );
+    }
+
+    #[test]
+    fn test_validation_report_display() {
+        fn check(errors: ValidationErrors) -> Result<(), Box<dyn Error>> {
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(ValidationReport(errors))?
+            }
+        }
+
+        let mut errors = ValidationErrors::new();
+        errors.insert(
+            "username".to_owned(),
+            vec![ValidationError::with_params("length", &[1, 20])],
+        );
+        errors.insert(
+            "password".to_owned(),
+            vec![
+                ValidationError::new("required"),
+                ValidationError::with_params("length", &[1, 20]),
+            ],
+        );
+
+        let error = check(errors).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "username: length(1, 20); password: required, length(1, 20)"
+        );
+        assert!(check(ValidationErrors::new()).is_ok());
+    }
 }
@@ -0,0 +1,103 @@
+use super::HasLength;
+
+/// Checks that exactly one of the given fields is present, returning a
+/// `ValidationError` with the code `exactly_one_of` and the field names as
+/// params otherwise.
+///
+/// A field is present if it's `Some` or has a non-zero length.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{exactly_one_of, validate, Length};
+///
+/// struct Contact {
+///     phone: Option<String>,
+///     email: Option<String>,
+/// }
+///
+/// let contact = Contact {
+///     phone: None,
+///     email: None,
+/// };
+///
+/// let mut errors = validate!(contact, {
+///     phone: [Length(1, 20)],
+///     email: [Length(1, 100)],
+/// });
+/// if let Some(error) = exactly_one_of!(contact, [phone, email]) {
+///     errors.entry("contact".to_owned()).or_insert_with(Vec::new).push(error);
+/// }
+///
+/// assert_eq!(errors.len(), 1);
+/// ```
+#[macro_export]
+macro_rules! exactly_one_of {
+    ($val:expr, [$($field:ident),+ $(,)*]) => ({
+        let present = 0 $(+ $crate::Present::is_present(&$val.$field) as usize)+;
+        if present == 1 {
+            None
+        } else {
+            Some($crate::ValidationError::with_params(
+                "exactly_one_of",
+                &[$(stringify!($field)),+],
+            ))
+        }
+    });
+}
+
+/// A field `exactly_one_of!` can tell is present.
+pub trait Present {
+    fn is_present(&self) -> bool;
+}
+
+impl<T> Present for Option<T> {
+    fn is_present(&self) -> bool {
+        self.is_some()
+    }
+}
+
+impl<T: HasLength> Present for T {
+    fn is_present(&self) -> bool {
+        self.length() > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ValidationError;
+
+    struct Contact {
+        phone: Option<String>,
+        email: String,
+    }
+
+    #[test]
+    fn test_exactly_one_of() {
+        let none = Contact {
+            phone: None,
+            email: "".to_owned(),
+        };
+        let phone = Contact {
+            phone: Some("123456".to_owned()),
+            email: "".to_owned(),
+        };
+        let email = Contact {
+            phone: None,
+            email: "user@example.com".to_owned(),
+        };
+        let both = Contact {
+            phone: Some("123456".to_owned()),
+            email: "user@example.com".to_owned(),
+        };
+
+        let expected = ValidationError::with_params("exactly_one_of", &["phone", "email"]);
+        assert_eq!(
+            exactly_one_of!(none, [phone, email]),
+            Some(expected.clone())
+        );
+        assert_eq!(exactly_one_of!(phone, [phone, email]), None);
+        assert_eq!(exactly_one_of!(email, [phone, email]), None);
+        assert_eq!(exactly_one_of!(both, [phone, email]), Some(expected));
+    }
+}
@@ -0,0 +1,115 @@
+use std::marker::PhantomData;
+
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates with a closure returning the error, for ad-hoc rules.
+///
+/// The type of the closure argument can't be inferred from the field, so it
+/// has to be written out. An `Option` field is validated with a closure over
+/// the inner value.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{validate, Func, Length, ValidationError};
+///
+/// struct User {
+///     password: String,
+/// }
+///
+/// let user = User {
+///     password: "pass word".to_owned(),
+/// };
+///
+/// let errors = validate!(user, {
+///     password: [
+///         Length(6, 20),
+///         Func(|p: &String| if p.contains(' ') {
+///             Some(ValidationError::new("no_spaces"))
+///         } else {
+///             None
+///         }),
+///     ],
+/// });
+///
+/// assert_eq!(errors["password"], vec![ValidationError::new("no_spaces")]);
+/// ```
+#[allow(non_snake_case)]
+pub fn Func<T, F>(f: F) -> FuncValidator<T, F>
+where
+    F: Fn(&T) -> Option<ValidationError>,
+{
+    FuncValidator(f, PhantomData)
+}
+
+pub struct FuncValidator<T, F>(F, PhantomData<fn(&T)>);
+
+impl<T, F> Validator<T> for FuncValidator<T, F>
+where
+    T: PlainValue,
+    F: Fn(&T) -> Option<ValidationError>,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        (self.0)(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate, Length};
+
+    fn has_digit(value: &String) -> Option<ValidationError> {
+        if value.chars().any(|c| c.is_ascii_digit()) {
+            None
+        } else {
+            Some(ValidationError::new("digit"))
+        }
+    }
+
+    #[test]
+    fn test_func_validator() {
+        let validator = Func(has_digit);
+        assert_matches!(validator.validate(&"pa55word".to_owned()), None);
+        assert_matches!(validator.validate(&"password".to_owned()), Some(err) => {
+            assert_eq!(err, ValidationError::new("digit"));
+        });
+    }
+
+    #[test]
+    fn test_func_validator_in_validate_macro() {
+        struct User {
+            password: String,
+            hint: Option<String>,
+        }
+
+        let user = User {
+            password: "password".to_owned(),
+            hint: None,
+        };
+        let errors = validate!(user, {
+            password: [
+                Length(6, 20),
+                Func(|p: &String| if p.chars().any(|c| c.is_ascii_digit()) {
+                    None
+                } else {
+                    Some(ValidationError::new("digit"))
+                }),
+            ],
+            hint: [Func(has_digit)],
+        });
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors["password"], vec![ValidationError::new("digit")]);
+
+        let user = User {
+            password: "pa55word".to_owned(),
+            hint: Some("no digits".to_owned()),
+        };
+        let errors = validate!(user, {
+            password: [Func(has_digit)],
+            hint: [Func(has_digit)],
+        });
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors["hint"], vec![ValidationError::new("digit")]);
+    }
+}
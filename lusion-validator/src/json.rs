@@ -0,0 +1,111 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates that a string is valid JSON.
+pub struct Json;
+
+impl<T> Validator<T> for Json
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        match serde_json::from_str::<serde_json::Value>(value.as_ref()) {
+            Ok(_) => None,
+            Err(_) => Some(ValidationError::new("json")),
+        }
+    }
+}
+
+/// Validates that a string is JSON that deserializes as `D`, e.g. a webhook
+/// payload of the expected shape.
+///
+/// The error carries the serde error message as its param.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{JsonAs, Validator};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Event {
+///     id: u64,
+/// }
+///
+/// assert!(JsonAs::<Event>().validate(&r#"{"id": 1}"#).is_none());
+/// assert!(JsonAs::<Event>().validate(&r#"{"id": "1"}"#).is_some());
+/// ```
+#[allow(non_snake_case)]
+pub fn JsonAs<D: DeserializeOwned>() -> JsonAsValidator<D> {
+    JsonAsValidator(PhantomData)
+}
+
+pub struct JsonAsValidator<D>(PhantomData<fn() -> D>);
+
+impl<T, D> Validator<T> for JsonAsValidator<D>
+where
+    T: AsRef<str> + PlainValue,
+    D: DeserializeOwned,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        match serde_json::from_str::<D>(value.as_ref()) {
+            Ok(_) => None,
+            Err(err) => Some(ValidationError::with_params(
+                "json_shape",
+                &[err.to_string()],
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_validator() {
+        for value in &[r#"{"a": [1, 2]}"#, "[]", "1.5", "\"text\"", "null"] {
+            assert_matches!(Json.validate(value), None);
+        }
+
+        for value in &["", "{", "{'a': 1}", "[1,]", "text"] {
+            let error = Json.validate(&value.to_string());
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::new("json"));
+            });
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Webhook {
+        event: String,
+        ids: Vec<u64>,
+    }
+
+    #[test]
+    fn test_json_as_validator() {
+        let validator = JsonAs::<Webhook>();
+        let payload = r#"{"event": "created", "ids": [1, 2]}"#.to_owned();
+        assert_matches!(validator.validate(&payload), None);
+
+        let error = validator.validate(&r#"{"event": "created", "ids": "1"}"#);
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err.code(), "json_shape");
+            let message = err.params()[0].as_str().unwrap();
+            assert!(message.contains("invalid type"), "{}", message);
+        });
+
+        let error = validator.validate(&r#"{"event": "created"}"#);
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err.code(), "json_shape");
+            let message = err.params()[0].as_str().unwrap();
+            assert!(message.contains("missing field") && message.contains("ids"), "{}", message);
+        });
+
+        assert_matches!(validator.validate(&"{"), Some(_));
+    }
+}
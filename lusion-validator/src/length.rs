@@ -1,38 +1,68 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use super::{ValidationError, Validator};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{PlainValue, ValidationError, Validator};
 
 #[allow(non_snake_case)]
 pub fn Length(min: usize, max: usize) -> LengthValidator {
-    LengthValidator(Some(min), Some(max))
+    LengthValidator(Some(min), Some(max), Unit::Bytes)
 }
 
 #[allow(non_snake_case)]
 pub fn MinLength(min: usize) -> LengthValidator {
-    LengthValidator(Some(min), None)
+    LengthValidator(Some(min), None, Unit::Bytes)
 }
 
 #[allow(non_snake_case)]
 pub fn MaxLength(max: usize) -> LengthValidator {
-    LengthValidator(None, Some(max))
+    LengthValidator(None, Some(max), Unit::Bytes)
+}
+
+pub struct LengthValidator(Option<usize>, Option<usize>, Unit);
+
+/// What the length of a string is measured in.
+#[derive(Clone, Copy)]
+enum Unit {
+    Bytes,
+    Chars,
 }
 
-pub struct LengthValidator(Option<usize>, Option<usize>);
+impl LengthValidator {
+    /// Measures strings in `char`s rather than bytes, so `"héllo"` has a
+    /// length of 5, not 6. Collections are measured the same either way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lusion_validator::{Length, Validator};
+    ///
+    /// assert!(Length(1, 5).validate(&"héllo!").is_some());
+    /// assert!(Length(1, 5).chars().validate(&"héllo").is_none());
+    /// ```
+    pub fn chars(self) -> Self {
+        LengthValidator(self.0, self.1, Unit::Chars)
+    }
+}
 
 impl<T> Validator<T> for LengthValidator
 where
     T: HasLength,
 {
     fn validate(&self, value: &T) -> Option<ValidationError> {
+        let length = match self.2 {
+            Unit::Bytes => value.length(),
+            Unit::Chars => value.char_length(),
+        };
         match (self.0, self.1) {
-            (Some(min), Some(max)) if min > value.length() || value.length() > max => {
+            (Some(min), Some(max)) if min > length || length > max => {
                 Some(ValidationError::with_params("length", &[min, max]))
             }
-            (Some(min), None) if min > value.length() => {
+            (Some(min), None) if min > length => {
                 Some(ValidationError::with_params("min_length", &[min]))
             }
-            (None, Some(max)) if value.length() > max => {
+            (None, Some(max)) if length > max => {
                 Some(ValidationError::with_params("max_length", &[max]))
             }
             _ => None,
@@ -40,26 +70,172 @@ where
     }
 }
 
+/// Validates the length of a string in grapheme clusters, the characters as
+/// a user perceives them, rather than in bytes.
+#[allow(non_snake_case)]
+pub fn GraphemeLength(min: usize, max: usize) -> GraphemeLengthValidator {
+    GraphemeLengthValidator(min, max)
+}
+
+pub struct GraphemeLengthValidator(usize, usize);
+
+impl<T> Validator<T> for GraphemeLengthValidator
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        let length = value.as_ref().graphemes(true).count();
+        if self.0 > length || length > self.1 {
+            Some(ValidationError::with_params(
+                "grapheme_length",
+                &[self.0, self.1],
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Validates the length of a string or byte slice in bytes, e.g. to fit a
+/// database column capped in bytes rather than characters.
+#[allow(non_snake_case)]
+pub fn ByteLength(min: usize, max: usize) -> ByteLengthValidator {
+    ByteLengthValidator(min, max)
+}
+
+pub struct ByteLengthValidator(usize, usize);
+
+impl<T> Validator<T> for ByteLengthValidator
+where
+    T: HasByteLength,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        let length = value.byte_length();
+        if self.0 > length || length > self.1 {
+            Some(ValidationError::with_params(
+                "byte_length",
+                &[self.0, self.1],
+            ))
+        } else {
+            None
+        }
+    }
+}
+
 pub trait HasLength {
     fn length(&self) -> usize;
+
+    /// The length in `char`s, which differs from `length` only for strings.
+    fn char_length(&self) -> usize {
+        self.length()
+    }
+}
+
+/// Implement `HasLength` for a newtype by delegating to one of its fields.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{impl_has_length, Length, Validator};
+///
+/// struct Username(String);
+///
+/// struct Nickname {
+///     value: String,
+/// }
+///
+/// impl_has_length!(Username);
+/// impl_has_length!(Nickname, value);
+///
+/// assert!(Length(1, 20).validate(&Username("user".to_owned())).is_none());
+/// assert!(Length(1, 20).validate(&Nickname { value: "".to_owned() }).is_some());
+/// ```
+#[macro_export]
+macro_rules! impl_has_length {
+    ($ty:ty) => {
+        $crate::impl_has_length!($ty, 0);
+    };
+    ($ty:ty, $field:tt) => {
+        impl $crate::HasLength for $ty {
+            fn length(&self) -> usize {
+                $crate::HasLength::length(&self.$field)
+            }
+
+            fn char_length(&self) -> usize {
+                $crate::HasLength::char_length(&self.$field)
+            }
+        }
+    };
+}
+
+/// Measures the length of any `AsRef<str>` value, for string types without a
+/// `HasLength` impl of their own.
+///
+/// A blanket `HasLength` impl over `AsRef<str>` would overlap with the
+/// collection impls, so the value is wrapped instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{Length, StrLength, Validator};
+///
+/// struct Email(String);
+///
+/// impl AsRef<str> for Email {
+///     fn as_ref(&self) -> &str {
+///         &self.0
+///     }
+/// }
+///
+/// let email = Email("a@example.com".to_owned());
+/// assert!(Length(1, 20).validate(&StrLength(&email)).is_none());
+/// ```
+pub struct StrLength<T>(pub T);
+
+impl<T: AsRef<str>> HasLength for StrLength<T> {
+    fn length(&self) -> usize {
+        self.0.as_ref().len()
+    }
+
+    fn char_length(&self) -> usize {
+        self.0.as_ref().chars().count()
+    }
+}
+
+impl<T: AsRef<str>> HasByteLength for StrLength<T> {
+    fn byte_length(&self) -> usize {
+        self.0.as_ref().len()
+    }
 }
 
 impl<'a> HasLength for &'a str {
     fn length(&self) -> usize {
         self.len()
     }
+
+    fn char_length(&self) -> usize {
+        self.chars().count()
+    }
 }
 
 impl HasLength for String {
     fn length(&self) -> usize {
         self.len()
     }
+
+    fn char_length(&self) -> usize {
+        self.chars().count()
+    }
 }
 
 impl<'a> HasLength for Cow<'a, str> {
     fn length(&self) -> usize {
         self.len()
     }
+
+    fn char_length(&self) -> usize {
+        self.chars().count()
+    }
 }
 
 impl<T> HasLength for Vec<T> {
@@ -68,6 +244,41 @@ impl<T> HasLength for Vec<T> {
     }
 }
 
+impl<'a, T> HasLength for &'a [T] {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+// Without const generics the arrays are covered up to 32 elements, like the
+// standard library's own trait impls.
+macro_rules! impl_has_length_for_arrays {
+    ($($n:expr),+) => {
+        $(
+            impl<T> HasLength for [T; $n] {
+                fn length(&self) -> usize {
+                    $n
+                }
+            }
+        )+
+    };
+}
+
+impl_has_length_for_arrays!(
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31, 32
+);
+
+impl<T: HasLength + ?Sized> HasLength for Box<T> {
+    fn length(&self) -> usize {
+        (**self).length()
+    }
+
+    fn char_length(&self) -> usize {
+        (**self).char_length()
+    }
+}
+
 impl<K, V> HasLength for HashMap<K, V> {
     fn length(&self) -> usize {
         self.len()
@@ -80,13 +291,65 @@ impl<V> HasLength for HashSet<V> {
     }
 }
 
+impl<K, V> HasLength for BTreeMap<K, V> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<V> HasLength for BTreeSet<V> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+pub trait HasByteLength {
+    fn byte_length(&self) -> usize;
+}
+
+impl<'a> HasByteLength for &'a str {
+    fn byte_length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl HasByteLength for String {
+    fn byte_length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<'a> HasByteLength for Cow<'a, str> {
+    fn byte_length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<'a> HasByteLength for &'a [u8] {
+    fn byte_length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl HasByteLength for Vec<u8> {
+    fn byte_length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: HasByteLength + ?Sized> HasByteLength for Box<T> {
+    fn byte_length(&self) -> usize {
+        (**self).byte_length()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     macro_rules! assert_validator_error {
         ([$($value:expr),*], $code:expr, min: $min:expr, max: $max:expr) => (
-            let validator = LengthValidator(Some($min), Some($max));
+            let validator = Length($min, $max);
             $(
                 let error = validator.validate($value);
                 assert_matches!(error, Some(err) => {
@@ -95,7 +358,7 @@ mod tests {
             )*
         );
         ([$($value:expr),*], $code:expr, min: $min:expr) => (
-            let validator = LengthValidator(Some($min), None);
+            let validator = MinLength($min);
             $(
                 let error = validator.validate($value);
                 assert_matches!(error, Some(err) => {
@@ -104,7 +367,7 @@ mod tests {
             )*
         );
         ([$($value:expr),*], $code:expr, max: $max:expr) => (
-            let validator = LengthValidator(None, Some($max));
+            let validator = MaxLength($max);
             $(
                 let error = validator.validate($value);
                 assert_matches!(error, Some(err) => {
@@ -114,6 +377,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_length_validator_with_newtype() {
+        struct Username(String);
+        impl_has_length!(Username);
+
+        let empty = Username("".to_owned());
+        let long = Username("123456".to_owned());
+        assert_validator_error!([&empty, &long], "length", min: 1, max: 4);
+        assert_validator_error!([&empty], "min_length", min: 1);
+        assert_validator_error!([&long], "max_length", max: 4);
+    }
+
     #[test]
     fn test_length_validator_with_str() {
         let empty: &'static str = "";
@@ -173,4 +448,135 @@ mod tests {
         assert_validator_error!([&empty], "min_length", min: 1);
         assert_validator_error!([&long], "max_length", max: 4);
     }
+
+    #[test]
+    fn test_length_validator_with_slice() {
+        let empty: &[i32] = &[];
+        let long: &[i32] = &[1, 2, 3, 4, 5, 6];
+        assert_validator_error!([&empty, &long], "length", min: 1, max: 4);
+        assert_validator_error!([&empty], "min_length", min: 1);
+        assert_validator_error!([&long], "max_length", max: 4);
+    }
+
+    #[test]
+    fn test_length_validator_with_array() {
+        let empty: [u8; 0] = [];
+        let long: [u8; 6] = [1, 2, 3, 4, 5, 6];
+        assert_validator_error!([&empty, &long], "length", min: 1, max: 4);
+        assert_validator_error!([&empty], "min_length", min: 1);
+        assert_validator_error!([&long], "max_length", max: 4);
+        assert_matches!(Length(4, 4).validate(&[0u8; 4]), None);
+    }
+
+    #[test]
+    fn test_length_validator_with_btreeset() {
+        let empty = BTreeSet::<String>::new();
+        let long = (0..10).map(|i| i.to_string()).collect::<BTreeSet<String>>();
+        assert_validator_error!([&empty, &long], "length", min: 1, max: 4);
+        assert_validator_error!([&empty], "min_length", min: 1);
+        assert_validator_error!([&long], "max_length", max: 4);
+    }
+
+    #[test]
+    fn test_length_validator_with_btreemap() {
+        let empty = BTreeMap::<usize, usize>::new();
+        let long = (0..10).map(|i| (i, i)).collect::<BTreeMap<usize, usize>>();
+        assert_validator_error!([&empty, &long], "length", min: 1, max: 4);
+        assert_validator_error!([&empty], "min_length", min: 1);
+        assert_validator_error!([&long], "max_length", max: 4);
+    }
+
+    #[test]
+    fn test_length_validator_with_str_length() {
+        use crate::validate;
+
+        struct Slug(String);
+
+        impl AsRef<str> for Slug {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        let empty = StrLength(Slug("".to_owned()));
+        let long = StrLength(Slug("123456".to_owned()));
+        assert_validator_error!([&empty, &long], "length", min: 1, max: 4);
+        assert_validator_error!([&empty], "min_length", min: 1);
+        assert_validator_error!([&long], "max_length", max: 4);
+
+        let slug = Slug("héllo".to_owned());
+        assert_matches!(Length(5, 5).chars().validate(&StrLength(&slug)), None);
+        assert_matches!(ByteLength(6, 6).validate(&StrLength(&slug)), None);
+
+        struct Post {
+            slug: Slug,
+        }
+
+        let post = Post { slug };
+        let errors = validate!(post, {
+            "slug": StrLength(&post.slug) => [Length(1, 4)],
+        });
+        assert_eq!(
+            errors["slug"],
+            vec![ValidationError::with_params("length", &[1, 4])]
+        );
+    }
+
+    #[test]
+    fn test_length_validator_with_chars() {
+        let cases = vec![("héllo", 6, 5), ("用户名", 9, 3), ("hello", 5, 5)];
+        for (value, bytes, chars) in cases {
+            let value = value.to_owned();
+            assert_eq!(value.length(), bytes);
+            assert_eq!(value.char_length(), chars);
+            assert_matches!(Length(chars, chars).chars().validate(&value), None);
+        }
+
+        let username = "用户名".to_owned();
+        assert_matches!(Length(1, 3).validate(&username), Some(err) => {
+            assert_eq!(err, ValidationError::with_params("length", &[1, 3]));
+        });
+        assert_matches!(MaxLength(3).chars().validate(&username), None);
+        assert_matches!(MaxLength(2).chars().validate(&username), Some(err) => {
+            assert_eq!(err, ValidationError::with_params("max_length", &[2]));
+        });
+        assert_matches!(MinLength(4).chars().validate(&Cow::from("héllo")), None);
+        assert_matches!(Length(1, 1).chars().validate(&vec!["héllo"]), None);
+    }
+
+    #[test]
+    fn test_byte_length_validator() {
+        let username = "用户名".to_owned();
+        assert_matches!(Length(1, 3).chars().validate(&username), None);
+        assert_matches!(ByteLength(1, 3).validate(&username), Some(err) => {
+            assert_eq!(err, ValidationError::with_params("byte_length", &[1, 3]));
+        });
+        assert_matches!(ByteLength(1, 9).validate(&username), None);
+
+        let bytes: &[u8] = &[0xde, 0xad, 0xbe, 0xef];
+        assert_matches!(ByteLength(4, 4).validate(&bytes), None);
+        assert_matches!(ByteLength(1, 2).validate(&bytes.to_vec()), Some(_));
+        assert_matches!(ByteLength(1, 2).validate(&Box::new("")), Some(_));
+    }
+
+    #[test]
+    fn test_grapheme_length_validator() {
+        // man, zero width joiner, woman, zero width joiner, girl
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(family.chars().count(), 5);
+        assert_eq!(family.len(), 18);
+        assert_matches!(GraphemeLength(1, 1).validate(&family), None);
+
+        // "e" followed by a combining acute accent
+        let accented = "e\u{301}".to_owned();
+        assert_eq!(accented.chars().count(), 2);
+        assert_eq!(accented.len(), 3);
+        assert_matches!(GraphemeLength(1, 1).validate(&accented), None);
+
+        let error = GraphemeLength(2, 4).validate(&accented);
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("grapheme_length", &[2, 4]));
+        });
+        assert_matches!(GraphemeLength(0, 1).validate(&"ab"), Some(_));
+    }
 }
@@ -5,62 +5,213 @@ extern crate serde_derive;
 #[macro_use]
 extern crate assert_matches;
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
+mod ascii;
+mod charset;
+mod combinator;
+mod each;
+mod enum_value;
 mod error;
+mod exclusive;
+mod func;
+mod json;
 mod length;
+mod locale;
+mod message;
+mod numeric;
+mod pattern;
+mod phone;
+mod range;
+mod required;
+mod url;
+mod validated;
 
-pub use self::error::{ValidationError, ValidationErrors};
+pub use self::ascii::*;
+pub use self::charset::*;
+pub use self::combinator::*;
+pub use self::each::*;
+pub use self::enum_value::*;
+pub use self::error::{to_json, ValidationError, ValidationErrors, ValidationReport};
+pub use self::exclusive::Present;
+pub use self::func::*;
+pub use self::json::*;
 pub use self::length::*;
+pub use self::locale::AcceptLanguageResolver;
+pub use self::message::MessageBag;
+pub use self::numeric::*;
+pub use self::pattern::*;
+pub use self::phone::*;
+pub use self::range::*;
+pub use self::required::*;
+pub use self::url::*;
+pub use self::validated::Validated;
 
 /// Validation a struct.
 ///
+/// Fields are keyed by their name. Nested fields and accessor methods are
+/// validated with a `"name": expr => [...]` entry, keyed by `"name"`.
+///
+/// A field whose type implements `Validate` is validated with `[Nested]`,
+/// which keeps its errors under the field name followed by a dot.
+///
+/// A field is compared to a sibling field with `[MustMatch(other)]`, failing
+/// with `must_match` when they differ. The field must be `PartialEq` to the
+/// other one.
+///
+/// A validator followed by `!`, like `[Required!, Length(1, 20)]`, is a gate:
+/// when it fails, the rest of the field's validators are skipped.
+///
 /// # Examples
 ///
 /// ```rust
 /// use lusion_validator::{validate, Length};
 ///
+/// struct Address {
+///     zip: String,
+/// }
+///
 /// struct User {
 ///     username: String,
 ///     password: String,
+///     address: Address,
 /// }
 ///
 /// let user = User {
 ///     username: "user".to_owned(),
 ///     password: "1234".to_owned(),
+///     address: Address {
+///         zip: "12345".to_owned(),
+///     },
 /// };
 ///
 /// let errors = validate!(user, {
 ///     username: [Length(1, 20)],
 ///     password: [Length(1, 20)],
+///     "address.zip": user.address.zip => [Length(5, 5)],
+/// });
+///
+/// assert!(errors.is_empty());
+///
+/// struct Signup {
+///     password: String,
+///     confirm_password: String,
+/// }
+///
+/// let signup = Signup {
+///     password: "1234".to_owned(),
+///     confirm_password: "1234".to_owned(),
+/// };
+///
+/// let errors = validate!(signup, {
+///     password: [Length(1, 20)],
+///     confirm_password: [MustMatch(password)],
 /// });
 ///
 /// assert!(errors.is_empty());
 /// ```
 #[macro_export]
 macro_rules! validate {
-    ($val:expr, {
-        $($field:ident: [$($validator:expr),+]),+ $(,)*
-    }) => ({
+    ($val:expr, { $($entries:tt)+ }) => ({
         use $crate::{ValidationErrors, Validator};
 
         let mut errors = ValidationErrors::new();
-
-        $(
-            $(
-                if let Some(error) = $validator.validate(&$val.$field) {
-                    errors.entry(stringify!($field))
-                        .or_insert_with(|| Vec::new())
-                        .push(error);
-                };
-            )+
-        )+
-
+        $crate::validate!(@entries errors, $val, $($entries)+);
         errors
     });
+
+    (@entries $errors:ident, $val:expr,) => {};
+    (@entries $errors:ident, $val:expr, $field:ident: [Nested] $(, $($rest:tt)*)?) => {
+        $crate::validate!(@nested $errors, stringify!($field), $val.$field);
+        $crate::validate!(@entries $errors, $val, $($($rest)*)?);
+    };
+    (@entries $errors:ident, $val:expr, $field:ident: [MustMatch($other:ident)] $(, $($rest:tt)*)?) => {
+        if $val.$field != $val.$other {
+            $errors.entry(String::from(stringify!($field)))
+                .or_insert_with(|| Vec::new())
+                .push($crate::ValidationError::new("must_match"));
+        }
+        $crate::validate!(@entries $errors, $val, $($($rest)*)?);
+    };
+    (@entries $errors:ident, $val:expr, $name:literal: $value:expr => [Nested] $(, $($rest:tt)*)?) => {
+        $crate::validate!(@nested $errors, $name, $value);
+        $crate::validate!(@entries $errors, $val, $($($rest)*)?);
+    };
+    (@entries $errors:ident, $val:expr, $field:ident: [$($validators:tt)+] $(, $($rest:tt)*)?) => {
+        $crate::validate!(@value $errors, stringify!($field), $val.$field, [] $($validators)+);
+        $crate::validate!(@entries $errors, $val, $($($rest)*)?);
+    };
+    (@entries $errors:ident, $val:expr, $name:literal: $value:expr => [$($validators:tt)+] $(, $($rest:tt)*)?) => {
+        $crate::validate!(@value $errors, $name, $value, [] $($validators)+);
+        $crate::validate!(@entries $errors, $val, $($($rest)*)?);
+    };
+
+    // Munches the validators one token at a time into `[...]`, as `!` can't
+    // follow an `expr` fragment.
+    (@value $errors:ident, $name:expr, $value:expr, []) => {};
+    (@value $errors:ident, $name:expr, $value:expr, [] , $($rest:tt)*) => {
+        $crate::validate!(@value $errors, $name, $value, [] $($rest)*);
+    };
+    (@value $errors:ident, $name:expr, $value:expr, [$($validator:tt)+]) => {
+        ($($validator)+).validate_field($name, &$value, &mut $errors);
+    };
+    (@value $errors:ident, $name:expr, $value:expr, [$($validator:tt)+] ! $(, $($rest:tt)*)?) => {
+        let before = $errors.get($name).map_or(0, Vec::len);
+        ($($validator)+).validate_field($name, &$value, &mut $errors);
+        if $errors.get($name).map_or(0, Vec::len) == before {
+            $crate::validate!(@value $errors, $name, $value, [] $($($rest)*)?);
+        }
+    };
+    (@value $errors:ident, $name:expr, $value:expr, [$($validator:tt)+] , $($rest:tt)*) => {
+        ($($validator)+).validate_field($name, &$value, &mut $errors);
+        $crate::validate!(@value $errors, $name, $value, [] $($rest)*);
+    };
+    (@value $errors:ident, $name:expr, $value:expr, [$($validator:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::validate!(@value $errors, $name, $value, [$($validator)* $next] $($rest)*);
+    };
+
+    (@nested $errors:ident, $name:expr, $value:expr) => {
+        for (field, errors) in $crate::Validate::validate(&$value) {
+            $errors.entry(format!("{}.{}", $name, field))
+                .or_insert_with(|| Vec::new())
+                .extend(errors);
+        }
+    };
+}
+
+/// A struct that validates its own fields, usually with `validate!`.
+pub trait Validate {
+    fn validate(&self) -> ValidationErrors;
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> ValidationErrors {
+        match *self {
+            Some(ref value) => value.validate(),
+            None => ValidationErrors::new(),
+        }
+    }
 }
 
+/// Marks a field to be validated with its `Validate` impl in `validate!`.
+pub struct Nested;
+
 /// A `Validator` trait for validate `T`
 pub trait Validator<T> {
     fn validate(&self, val: &T) -> Option<ValidationError>;
+
+    /// Adds the errors of the field `name` to `errors`, as `validate!` does.
+    fn validate_field(&self, name: &str, val: &T, errors: &mut ValidationErrors) {
+        if let Some(error) = self.validate(val) {
+            errors
+                .entry(name.to_owned())
+                .or_insert_with(Vec::new)
+                .push(error);
+        }
+    }
 }
 
 impl<T, V> Validator<Option<T>> for V
@@ -73,8 +224,92 @@ where
             None => None,
         }
     }
+
+    fn validate_field(&self, name: &str, value: &Option<T>, errors: &mut ValidationErrors) {
+        if let Some(ref value) = *value {
+            self.validate_field(name, value, errors);
+        }
+    }
+}
+
+impl<T, V> Validator<Rc<T>> for V
+where
+    V: Validator<T>,
+{
+    fn validate(&self, value: &Rc<T>) -> Option<ValidationError> {
+        self.validate(&**value)
+    }
+
+    fn validate_field(&self, name: &str, value: &Rc<T>, errors: &mut ValidationErrors) {
+        self.validate_field(name, &**value, errors);
+    }
 }
 
+impl<T, V> Validator<Arc<T>> for V
+where
+    V: Validator<T>,
+{
+    fn validate(&self, value: &Arc<T>) -> Option<ValidationError> {
+        self.validate(&**value)
+    }
+
+    fn validate_field(&self, name: &str, value: &Arc<T>, errors: &mut ValidationErrors) {
+        self.validate_field(name, &**value, errors);
+    }
+}
+
+/// A value validated as is, rather than through the `Option`, `Rc` or `Arc`
+/// wrapping it.
+///
+/// Validators that are generic over the value, like `And` and `Or`, only
+/// validate a `PlainValue`, as they would otherwise overlap with the blanket
+/// impls for the wrappers. So do the string validators, like `Pattern`, which
+/// validate any `AsRef<str> + PlainValue`: `Rc<str>` is `AsRef<str>`, and std
+/// could make `Option<T>` one too. Implement it for your own types to
+/// validate them with those validators.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{Ascii, PlainValue, Validator};
+///
+/// struct Email(String);
+///
+/// impl AsRef<str> for Email {
+///     fn as_ref(&self) -> &str {
+///         &self.0
+///     }
+/// }
+///
+/// impl PlainValue for Email {}
+///
+/// let email = Email("a@example.com".to_owned());
+/// assert!(Ascii.validate(&email).is_none());
+/// ```
+pub trait PlainValue {}
+
+macro_rules! impl_plain_value {
+    ($($ty:ty),+) => {
+        $(impl PlainValue for $ty {})+
+    };
+}
+
+impl_plain_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, bool, char, String);
+
+impl PlainValue for str {}
+
+impl<'a> PlainValue for &'a str {}
+
+impl<'a> PlainValue for Cow<'a, str> {}
+
+impl<T: PlainValue + ?Sized> PlainValue for Box<T> {}
+
+impl<T> PlainValue for Vec<T> {}
+
+impl<K, V> PlainValue for HashMap<K, V> {}
+
+impl<T> PlainValue for HashSet<T> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +335,37 @@ mod tests {
         assert_matches!(error, None);
     }
 
+    #[test]
+    fn test_validator_for_smart_pointers() {
+        struct User {
+            username: Box<String>,
+            nickname: Rc<String>,
+            password: Arc<String>,
+        }
+
+        let user = User {
+            username: Box::new("".to_owned()),
+            nickname: Rc::new("nickname".to_owned()),
+            password: Arc::new("".to_owned()),
+        };
+
+        let errors = validate!(user, {
+            username: [Length(1, 20)],
+            nickname: [Length(1, 20)],
+            password: [Length(1, 20)],
+        });
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors["username"],
+            vec![ValidationError::with_params("length", &[1, 20])]
+        );
+        assert_eq!(
+            errors["password"],
+            vec![ValidationError::with_params("length", &[1, 20])]
+        );
+    }
+
     #[test]
     fn test_validate_macro() {
         struct User {
@@ -119,4 +385,219 @@ mod tests {
 
         assert!(errors.is_empty());
     }
+
+    #[test]
+    fn test_validate_macro_gate() {
+        struct User {
+            username: Option<String>,
+            nickname: Option<String>,
+            password: String,
+        }
+
+        let user = User {
+            username: Some("".to_owned()),
+            nickname: Some("".to_owned()),
+            password: "".to_owned(),
+        };
+
+        let errors = validate!(user, {
+            username: [Required!, Length(1, 20), Pattern("^[a-z]+$")],
+            nickname: [Required, Length(1, 20)],
+            password: [Length(4, 20)!, Length(1, 20),],
+        });
+
+        assert_eq!(errors["username"], vec![ValidationError::new("required")]);
+        assert_eq!(errors["nickname"].len(), 2);
+        assert_eq!(
+            errors["password"],
+            vec![ValidationError::with_params("length", &[4, 20])]
+        );
+
+        let user = User {
+            username: Some("User".to_owned()),
+            nickname: None,
+            password: "1234".to_owned(),
+        };
+
+        let errors = validate!(user, {
+            username: [Required!, Length(1, 20)!, Pattern("^[a-z]+$")],
+            nickname: [Required!, Length(1, 20)],
+            "password": user.password => [Length(1, 3)!, Length(5, 20)!],
+        });
+
+        assert_eq!(errors["username"].len(), 1);
+        assert_eq!(errors["username"][0].code(), "pattern");
+        assert_eq!(errors["nickname"], vec![ValidationError::new("required")]);
+        assert_eq!(
+            errors["password"],
+            vec![ValidationError::with_params("length", &[1, 3])]
+        );
+    }
+
+    #[test]
+    fn test_validate_macro_preserves_field_order() {
+        struct User {
+            username: String,
+            password: String,
+            nickname: String,
+        }
+
+        let user = User {
+            username: "".to_owned(),
+            password: "".to_owned(),
+            nickname: "".to_owned(),
+        };
+
+        let errors = validate!(user, {
+            username: [Length(1, 20)],
+            password: [Length(1, 20)],
+            nickname: [Length(1, 20)],
+        });
+
+        let fields = errors.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(fields, vec!["username", "password", "nickname"]);
+
+        let json = serde_json::to_string(&errors).unwrap();
+        assert_eq!(
+            json,
+            r#"{"username":[{"code":"length","params":[1,20]}],"password":[{"code":"length","params":[1,20]}],"nickname":[{"code":"length","params":[1,20]}]}"#
+        );
+    }
+
+    #[test]
+    fn test_validate_macro_nested() {
+        struct Geo {
+            lat: String,
+        }
+
+        impl Validate for Geo {
+            fn validate(&self) -> ValidationErrors {
+                validate!(self, {
+                    lat: [Length(1, 10)],
+                })
+            }
+        }
+
+        struct Address {
+            zip: String,
+            geo: Option<Geo>,
+        }
+
+        impl Validate for Address {
+            fn validate(&self) -> ValidationErrors {
+                validate!(self, {
+                    zip: [Length(5, 5)],
+                    geo: [Nested],
+                })
+            }
+        }
+
+        struct User {
+            username: String,
+            address: Address,
+        }
+
+        let user = User {
+            username: "".to_owned(),
+            address: Address {
+                zip: "123".to_owned(),
+                geo: Some(Geo { lat: "".to_owned() }),
+            },
+        };
+
+        let errors = validate!(user, {
+            username: [Length(1, 20)],
+            address: [Nested],
+            "home": user.address => [Nested],
+        });
+
+        let fields = errors.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                "username",
+                "address.zip",
+                "address.geo.lat",
+                "home.zip",
+                "home.geo.lat"
+            ]
+        );
+        assert_eq!(
+            errors["address.geo.lat"],
+            vec![ValidationError::with_params("length", &[1, 10])]
+        );
+
+        let json = serde_json::to_value(&errors).unwrap();
+        assert_eq!(json["address.zip"][0]["code"], "length");
+    }
+
+    #[test]
+    fn test_validate_macro_must_match() {
+        struct Signup {
+            password: String,
+            confirm_password: String,
+        }
+
+        let signup = Signup {
+            password: "1234".to_owned(),
+            confirm_password: "1234".to_owned(),
+        };
+        let errors = validate!(signup, {
+            password: [Length(4, 20)],
+            confirm_password: [MustMatch(password)],
+        });
+        assert!(errors.is_empty());
+
+        let signup = Signup {
+            password: "1234".to_owned(),
+            confirm_password: "4321".to_owned(),
+        };
+        let errors = validate!(signup, {
+            password: [Length(4, 20)],
+            confirm_password: [MustMatch(password)],
+        });
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors["confirm_password"],
+            vec![ValidationError::new("must_match")]
+        );
+    }
+
+    #[test]
+    fn test_validate_macro_nested_field() {
+        struct Address {
+            zip: String,
+        }
+
+        impl Address {
+            fn street(&self) -> &str {
+                ""
+            }
+        }
+
+        struct User {
+            username: String,
+            address: Address,
+        }
+
+        let user = User {
+            username: "user".to_owned(),
+            address: Address {
+                zip: "123".to_owned(),
+            },
+        };
+
+        let errors = validate!(user, {
+            username: [Length(1, 20)],
+            "address.zip": user.address.zip => [Length(5, 5)],
+            "address.street": user.address.street() => [MinLength(1)],
+        });
+
+        let fields = errors.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(fields, vec!["address.zip", "address.street"]);
+        assert_eq!(
+            errors["address.zip"],
+            vec![ValidationError::with_params("length", &[5, 5])]
+        );
+    }
 }
@@ -0,0 +1,210 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use super::{MessageBag, ValidationError};
+
+/// Resolves validation messages in the locale negotiated from an
+/// `Accept-Language` header.
+///
+/// The built-in `en` and `zh` tables are registered by default, `en` being
+/// the default locale.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{AcceptLanguageResolver, ValidationError};
+///
+/// let resolver = AcceptLanguageResolver::default();
+/// let error = ValidationError::with_params("length", &[1, 20]);
+///
+/// assert_eq!(
+///     resolver.message(&error, Some("zh-CN,zh;q=0.9,en;q=0.8")),
+///     "长度必须在 1 到 20 个字符之间"
+/// );
+/// assert_eq!(
+///     resolver.message(&error, None),
+///     "must be between 1 and 20 characters"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct AcceptLanguageResolver {
+    default_locale: Cow<'static, str>,
+    bags: HashMap<Cow<'static, str>, MessageBag>,
+}
+
+impl AcceptLanguageResolver {
+    /// Creates a resolver with `default_locale` and no table registered.
+    pub fn new<L: Into<Cow<'static, str>>>(default_locale: L) -> Self {
+        AcceptLanguageResolver {
+            default_locale: into_lowercase(default_locale.into()),
+            bags: HashMap::new(),
+        }
+    }
+
+    /// Registers the messages of `locale`, replacing the existing ones.
+    ///
+    /// `locale` is a language tag like `zh` or `zh-TW`, matched
+    /// case-insensitively.
+    pub fn register<L>(&mut self, locale: L, bag: MessageBag) -> &mut Self
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        self.bags.insert(into_lowercase(locale.into()), bag);
+        self
+    }
+
+    /// The messages of the most preferred registered locale of
+    /// `accept_language`, falling back to the default locale.
+    ///
+    /// A language range matches a registered locale with the same tag, or
+    /// with its primary subtag, e.g. `zh-CN` falls back to `zh`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no table is registered for the default locale.
+    pub fn resolve(&self, accept_language: Option<&str>) -> &MessageBag {
+        accept_language
+            .and_then(|header| {
+                parse_accept_language(header)
+                    .into_iter()
+                    .filter_map(|range| self.lookup(&range))
+                    .next()
+            })
+            .or_else(|| self.bags.get(&*self.default_locale))
+            .expect("no messages registered for the default locale")
+    }
+
+    /// The message of `error` in the negotiated locale.
+    pub fn message(&self, error: &ValidationError, accept_language: Option<&str>) -> String {
+        error.message(self.resolve(accept_language))
+    }
+
+    fn lookup(&self, range: &str) -> Option<&MessageBag> {
+        if range == "*" {
+            return self.bags.get(&*self.default_locale);
+        }
+        self.bags.get(range).or_else(|| {
+            let primary = range.split('-').next().unwrap_or(range);
+            self.bags.get(primary)
+        })
+    }
+}
+
+/// Registers the built-in `en` and `zh` tables, defaulting to `en`.
+impl Default for AcceptLanguageResolver {
+    fn default() -> Self {
+        let mut resolver = AcceptLanguageResolver::new("en");
+        for &locale in &["en", "zh"] {
+            resolver.register(locale, MessageBag::for_locale(locale).unwrap());
+        }
+        resolver
+    }
+}
+
+fn into_lowercase(locale: Cow<'static, str>) -> Cow<'static, str> {
+    if locale.chars().any(|c| c.is_ascii_uppercase()) {
+        Cow::Owned(locale.to_ascii_lowercase())
+    } else {
+        locale
+    }
+}
+
+/// The lowercased language ranges of an `Accept-Language` header, most
+/// preferred first, without the refused (`q=0`) ones.
+fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut ranges = header
+        .split(',')
+        .filter_map(|item| {
+            let mut params = item.split(';').map(str::trim);
+            let range = params.next().filter(|range| !range.is_empty())?;
+            let quality = params
+                .filter(|param| param.starts_with("q="))
+                .map(|param| param[2..].parse::<f32>().unwrap_or(0.0))
+                .next()
+                .unwrap_or(1.0);
+            if quality > 0.0 {
+                Some((range.to_ascii_lowercase(), quality))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    // stable, so equally preferred ranges keep the header order
+    ranges.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    ranges.into_iter().map(|(range, _)| range).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accept_language() {
+        assert_eq!(
+            parse_accept_language("en;q=0.5, zh-CN, fr;q=0, de;q=0.8"),
+            vec!["zh-cn", "de", "en"]
+        );
+        assert_eq!(
+            parse_accept_language("da, en-gb;q=0.8, en;q=0.8"),
+            vec!["da", "en-gb", "en"]
+        );
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn test_accept_language_resolver() {
+        let resolver = AcceptLanguageResolver::default();
+        let error = ValidationError::with_params("length", &[1, 20]);
+        let en = "must be between 1 and 20 characters";
+        let zh = "长度必须在 1 到 20 个字符之间";
+
+        assert_eq!(resolver.message(&error, None), en);
+        assert_eq!(resolver.message(&error, Some("")), en);
+        assert_eq!(resolver.message(&error, Some("en-US")), en);
+        assert_eq!(resolver.message(&error, Some("zh")), zh);
+        assert_eq!(resolver.message(&error, Some("ZH-cn,en;q=0.8")), zh);
+        assert_eq!(resolver.message(&error, Some("en;q=0.5, zh;q=0.9")), zh);
+        assert_eq!(resolver.message(&error, Some("fr, zh;q=0.1")), zh);
+        assert_eq!(resolver.message(&error, Some("zh;q=0, fr")), en);
+        assert_eq!(resolver.message(&error, Some("*")), en);
+    }
+
+    #[test]
+    fn test_accept_language_resolver_register() {
+        let mut fr = MessageBag::new();
+        fr.set("length", "doit contenir entre {0} et {1} caractères");
+        let mut zh_tw = MessageBag::for_locale("zh").unwrap();
+        zh_tw.set("length", "長度必須在 {0} 到 {1} 個字元之間");
+
+        let mut resolver = AcceptLanguageResolver::default();
+        resolver.register("fr", fr).register("zh-TW", zh_tw);
+
+        let error = ValidationError::with_params("length", &[1, 20]);
+        assert_eq!(
+            resolver.message(&error, Some("fr-CA")),
+            "doit contenir entre 1 et 20 caractères"
+        );
+        assert_eq!(
+            resolver.message(&error, Some("zh-tw")),
+            "長度必須在 1 到 20 個字元之間"
+        );
+        assert_eq!(
+            resolver.message(&error, Some("zh-CN")),
+            "长度必须在 1 到 20 个字符之间"
+        );
+        assert_eq!(
+            resolver.message(&ValidationError::new("required"), Some("fr")),
+            "required"
+        );
+    }
+
+    #[test]
+    fn test_accept_language_resolver_default_locale() {
+        let mut resolver = AcceptLanguageResolver::new("zh");
+        resolver.register("zh", MessageBag::for_locale("zh").unwrap());
+
+        let error = ValidationError::new("required");
+        assert_eq!(resolver.message(&error, Some("en")), "不能为空");
+        assert_eq!(resolver.message(&error, None), "不能为空");
+    }
+}
@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::ValidationError;
+
+const DEFAULT_MESSAGES: &[(&str, &str)] = &[
+    ("ascii", "must only contain ASCII characters"),
+    (
+        "ascii_printable",
+        "must only contain printable ASCII characters",
+    ),
+    ("byte_length", "must be between {0} and {1} bytes"),
+    ("charset", "must only contain the allowed characters"),
+    ("each", "has invalid items"),
+    ("email", "must be a valid email address"),
+    ("exactly_one_of", "exactly one of the fields is required"),
+    ("grapheme_length", "must be between {0} and {1} characters"),
+    ("invalid_enum", "is not one of the allowed values"),
+    ("json", "must be valid JSON"),
+    ("json_shape", "must be JSON of the expected shape: {0}"),
+    ("length", "must be between {0} and {1} characters"),
+    ("max_length", "must be at most {0} characters"),
+    ("max_value", "must be at most {0}"),
+    ("min_length", "must be at least {0} characters"),
+    ("min_value", "must be at least {0}"),
+    ("must_match", "doesn't match"),
+    ("not_a_number", "must be a number"),
+    ("or", "is invalid"),
+    ("pattern", "must match {0}"),
+    ("phone", "must be a phone number like +14155550123"),
+    ("range", "must be between {0} and {1}"),
+    ("required", "is required"),
+    ("url", "must be a valid URL"),
+];
+
+const ZH_MESSAGES: &[(&str, &str)] = &[
+    ("ascii", "只能包含 ASCII 字符"),
+    ("ascii_printable", "只能包含可打印的 ASCII 字符"),
+    ("byte_length", "长度必须在 {0} 到 {1} 字节之间"),
+    ("charset", "只能包含允许的字符"),
+    ("each", "包含无效的项"),
+    ("email", "必须是有效的电子邮件地址"),
+    ("exactly_one_of", "必须且只能填写其中一个字段"),
+    ("grapheme_length", "长度必须在 {0} 到 {1} 个字符之间"),
+    ("invalid_enum", "不是允许的值"),
+    ("json", "必须是有效的 JSON"),
+    ("json_shape", "必须是预期结构的 JSON：{0}"),
+    ("length", "长度必须在 {0} 到 {1} 个字符之间"),
+    ("max_length", "最多 {0} 个字符"),
+    ("max_value", "不能大于 {0}"),
+    ("min_length", "至少 {0} 个字符"),
+    ("min_value", "不能小于 {0}"),
+    ("must_match", "不匹配"),
+    ("not_a_number", "必须是数字"),
+    ("or", "无效"),
+    ("pattern", "必须匹配 {0}"),
+    ("phone", "必须是类似 +8613800138000 的电话号码"),
+    ("range", "必须在 {0} 到 {1} 之间"),
+    ("required", "不能为空"),
+    ("url", "必须是有效的 URL"),
+];
+
+/// Message templates of the validation error codes.
+///
+/// `{0}`, `{1}`, ... in a template are replaced with the error's params.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{MessageBag, ValidationError};
+///
+/// let mut bag = MessageBag::default();
+/// bag.set("required", "can't be blank");
+///
+/// let error = ValidationError::with_params("length", &[1, 20]);
+/// assert_eq!(error.message(&bag), "must be between 1 and 20 characters");
+/// assert_eq!(ValidationError::new("required").message(&bag), "can't be blank");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageBag {
+    templates: HashMap<Cow<'static, str>, Cow<'static, str>>,
+}
+
+impl MessageBag {
+    /// Creates a bag without any template.
+    pub fn new() -> Self {
+        MessageBag {
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Sets the template of `code`, replacing the existing one.
+    pub fn set<C, T>(&mut self, code: C, template: T) -> &mut Self
+    where
+        C: Into<Cow<'static, str>>,
+        T: Into<Cow<'static, str>>,
+    {
+        self.templates.insert(code.into(), template.into());
+        self
+    }
+
+    pub fn get(&self, code: &str) -> Option<&str> {
+        self.templates.get(code).map(|template| &**template)
+    }
+
+    /// The built-in messages of `locale`, `en` or `zh`.
+    pub fn for_locale(locale: &str) -> Option<Self> {
+        let messages = match locale {
+            "en" => DEFAULT_MESSAGES,
+            "zh" => ZH_MESSAGES,
+            _ => return None,
+        };
+        let mut bag = MessageBag::new();
+        for &(code, template) in messages {
+            bag.set(code, template);
+        }
+        Some(bag)
+    }
+}
+
+/// The English messages of the built-in validators.
+impl Default for MessageBag {
+    fn default() -> Self {
+        MessageBag::for_locale("en").unwrap()
+    }
+}
+
+impl ValidationError {
+    /// The message of the error in `bag`, falling back to the code and the
+    /// params for an unknown code.
+    pub fn message(&self, bag: &MessageBag) -> String {
+        let template = match bag.get(self.code()) {
+            Some(template) => template,
+            None => return self.to_string(),
+        };
+        let mut message = template.to_owned();
+        for (i, param) in self.params().iter().enumerate() {
+            let value = match param {
+                Value::String(s) => s.clone(),
+                value => value.to_string(),
+            };
+            message = message.replace(&format!("{{{}}}", i), &value);
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_interpolation() {
+        let bag = MessageBag::default();
+
+        let error = ValidationError::with_params("length", &[1, 20]);
+        assert_eq!(error.message(&bag), "must be between 1 and 20 characters");
+
+        let error = ValidationError::with_params("max_length", &[20]);
+        assert_eq!(error.message(&bag), "must be at most 20 characters");
+
+        let error = ValidationError::with_params("pattern", &["^[A-Z]{3}$"]);
+        assert_eq!(error.message(&bag), "must match ^[A-Z]{3}$");
+    }
+
+    #[test]
+    fn test_message_override() {
+        let mut bag = MessageBag::default();
+        bag.set("max_length", "is longer than {0}");
+
+        let error = ValidationError::with_params("max_length", &[20]);
+        assert_eq!(error.message(&bag), "is longer than 20");
+    }
+
+    #[test]
+    fn test_message_built_in_locales() {
+        let en = MessageBag::for_locale("en").unwrap();
+        let zh = MessageBag::for_locale("zh").unwrap();
+        assert_eq!(ZH_MESSAGES.len(), DEFAULT_MESSAGES.len());
+        for &(code, _) in DEFAULT_MESSAGES {
+            assert!(en.get(code).is_some());
+            assert!(zh.get(code).is_some(), "missing zh message: {}", code);
+        }
+        assert!(MessageBag::for_locale("fr").is_none());
+    }
+
+    #[test]
+    fn test_message_unknown_code() {
+        let bag = MessageBag::default();
+
+        assert_eq!(ValidationError::new("no_spaces").message(&bag), "no_spaces");
+        assert_eq!(
+            ValidationError::with_params("max_items", &[5]).message(&MessageBag::new()),
+            "max_items(5)"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates that a string holds a number within the inclusive bounds.
+#[allow(non_snake_case)]
+pub fn NumericRange(min: f64, max: f64) -> NumericRangeValidator {
+    NumericRangeValidator(min, max)
+}
+
+pub struct NumericRangeValidator(f64, f64);
+
+impl<T> Validator<T> for NumericRangeValidator
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        match value.as_ref().parse::<f64>() {
+            Ok(number) if number.is_finite() => {
+                if self.0 > number || number > self.1 {
+                    Some(ValidationError::with_params("range", &[self.0, self.1]))
+                } else {
+                    None
+                }
+            }
+            _ => Some(ValidationError::new("not_a_number")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_range_validator_with_non_numeric() {
+        let validator = NumericRange(1.0, 100.0);
+        for value in &["", "abc", "4 2", "NaN", "inf"] {
+            let error = validator.validate(value);
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::new("not_a_number"));
+            });
+        }
+    }
+
+    #[test]
+    fn test_numeric_range_validator_out_of_range() {
+        let validator = NumericRange(1.0, 100.0);
+        for value in &["0", "-5", "100.5", "1000"] {
+            let error = validator.validate(&value.to_string());
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::with_params("range", &[1.0, 100.0]));
+            });
+        }
+    }
+
+    #[test]
+    fn test_numeric_range_validator_in_range() {
+        let validator = NumericRange(1.0, 100.0);
+        for value in &["1", "42", "99.9", "100"] {
+            assert_matches!(validator.validate(value), None);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use regex::Regex;
+
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates that a string matches a regular expression.
+///
+/// The pattern is compiled once, so a validator can be kept around and
+/// reused. Patterns aren't anchored; use `^` and `$` to match the whole
+/// string.
+///
+/// # Panics
+///
+/// Panics if a `&str` pattern isn't a valid regular expression.
+#[allow(non_snake_case)]
+pub fn Pattern<P: IntoRegex>(pattern: P) -> PatternValidator {
+    PatternValidator(pattern.into_regex())
+}
+
+#[derive(Debug, Clone)]
+pub struct PatternValidator(Arc<Regex>);
+
+/// A pattern `Pattern` accepts: a `&'static str` or a compiled `Regex`.
+pub trait IntoRegex {
+    fn into_regex(self) -> Arc<Regex>;
+}
+
+impl IntoRegex for &'static str {
+    fn into_regex(self) -> Arc<Regex> {
+        Arc::new(Regex::new(self).expect("invalid pattern"))
+    }
+}
+
+impl IntoRegex for Regex {
+    fn into_regex(self) -> Arc<Regex> {
+        Arc::new(self)
+    }
+}
+
+impl IntoRegex for Arc<Regex> {
+    fn into_regex(self) -> Arc<Regex> {
+        self
+    }
+}
+
+impl<T> Validator<T> for PatternValidator
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        if self.0.is_match(value.as_ref()) {
+            None
+        } else {
+            Some(ValidationError::with_params("pattern", &[self.0.as_str()]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_validator() {
+        let validator = Pattern(r"^[A-Z]{3}$");
+        assert_matches!(validator.validate(&"ABC"), None);
+        assert_matches!(validator.validate(&"XYZ".to_owned()), None);
+        assert_matches!(validator.validate(&Box::<str>::from("XYZ")), None);
+        assert_matches!(validator.validate(&Some("XYZ")), None);
+
+        let error = validator.validate(&"AB1");
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("pattern", &[r"^[A-Z]{3}$"]));
+        });
+    }
+
+    #[test]
+    fn test_pattern_validator_from_regex() {
+        let validator = Pattern(Regex::new(r"^\d+$").unwrap());
+        assert_matches!(validator.validate(&"123"), None);
+        assert_matches!(validator.validate(&"12a"), Some(_));
+    }
+
+    #[test]
+    fn test_pattern_validator_anchoring() {
+        let unanchored = Pattern(r"[A-Z]{3}");
+        assert_matches!(unanchored.validate(&"xABCx"), None);
+
+        let anchored = Pattern(r"^[A-Z]{3}$");
+        assert_matches!(anchored.validate(&"xABCx"), Some(_));
+        assert_matches!(anchored.validate(&"ABCD"), Some(_));
+    }
+}
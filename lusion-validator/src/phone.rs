@@ -0,0 +1,52 @@
+use super::{PlainValue, ValidationError, Validator};
+
+/// Validates that a string is an E.164 phone number: a `+` followed by up
+/// to 15 digits, the first of which isn't zero.
+pub struct Phone;
+
+impl<T> Validator<T> for Phone
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        let value = value.as_ref();
+        let valid = value.starts_with('+') && {
+            let digits = &value[1..];
+            !digits.is_empty()
+                && digits.len() <= 15
+                && !digits.starts_with('0')
+                && digits.bytes().all(|b| b.is_ascii_digit())
+        };
+        if valid {
+            None
+        } else {
+            Some(ValidationError::new("phone"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phone_validator() {
+        for value in &["+14155550123", "+8613800138000", "+123456789012345"] {
+            assert_matches!(Phone.validate(value), None);
+        }
+
+        for value in &[
+            "415-555-0123",
+            "+0123",
+            "",
+            "+",
+            "+1234567890123456",
+            "+1 415 555",
+        ] {
+            let error = Phone.validate(&value.to_string());
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::new("phone"));
+            });
+        }
+    }
+}
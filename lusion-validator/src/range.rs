@@ -0,0 +1,125 @@
+use serde::Serialize;
+
+use super::{ValidationError, Validator};
+
+/// Validates that a value is within the inclusive bounds, either of which
+/// may be left open.
+///
+/// A value that can't be compared with a bound, such as a `NaN`, is out of
+/// range.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{Range, Validator};
+///
+/// assert!(Range(Some(18), Some(120)).validate(&42).is_none());
+/// assert!(Range(Some(0.0), None).validate(&-0.5).is_some());
+/// ```
+#[allow(non_snake_case)]
+pub fn Range<T>(min: Option<T>, max: Option<T>) -> RangeValidator<T> {
+    RangeValidator(min, max)
+}
+
+pub struct RangeValidator<T>(Option<T>, Option<T>);
+
+impl<T> Validator<T> for RangeValidator<T>
+where
+    T: RangeBound,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        match (&self.0, &self.1) {
+            // Checked as `<=`, which is false for unordered values.
+            (Some(min), Some(max)) if min <= value && value <= max => None,
+            (Some(min), Some(max)) => Some(ValidationError::with_params("range", &[min, max])),
+            (Some(min), None) if min <= value => None,
+            (Some(min), None) => Some(ValidationError::with_params("min_value", &[min])),
+            (None, Some(max)) if value <= max => None,
+            (None, Some(max)) => Some(ValidationError::with_params("max_value", &[max])),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A value `Range` can compare against its bounds.
+///
+/// A blanket impl over `PartialOrd` would overlap with the validators over
+/// `Option`, `Rc` and `Arc`, so comparable types opt in with this trait.
+pub trait RangeBound: PartialOrd + Serialize {}
+
+macro_rules! impl_range_bound {
+    ($($ty:ty),+) => {
+        $(impl RangeBound for $ty {})+
+    };
+}
+
+impl_range_bound!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, char);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+
+    #[test]
+    fn test_range_validator_with_integers() {
+        let validator = Range(Some(18), Some(120));
+        for value in &[18, 42, 120] {
+            assert_matches!(validator.validate(value), None);
+        }
+        for value in &[17, 121, -1] {
+            let error = validator.validate(value);
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::with_params("range", &[18, 120]));
+            });
+        }
+
+        let error = Range(Some(1u64), None).validate(&0u64);
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("min_value", &[1]));
+        });
+        assert_matches!(Range(Some(1u64), None).validate(&u64::MAX), None);
+    }
+
+    #[test]
+    fn test_range_validator_with_floats() {
+        let validator = Range(None, Some(1.5));
+        assert_matches!(validator.validate(&1.5), None);
+        assert_matches!(validator.validate(&-1e9), None);
+
+        let error = validator.validate(&1.500_001);
+        assert_matches!(error, Some(err) => {
+            assert_eq!(err, ValidationError::with_params("max_value", &[1.5]));
+        });
+    }
+
+    #[test]
+    fn test_range_validator_with_nan() {
+        let error = Range(Some(0.0), Some(1.0)).validate(&f64::NAN);
+        assert_matches!(error, Some(_));
+        assert_matches!(Range(Some(0.0), None).validate(&f64::NAN), Some(_));
+        assert_matches!(Range(None, Some(1.0)).validate(&f32::NAN), Some(_));
+        assert_matches!(Range(Some(f64::NAN), None).validate(&0.5), Some(_));
+    }
+
+    #[test]
+    fn test_range_validator_with_option() {
+        struct User {
+            age: Option<u8>,
+        }
+
+        let user = User { age: Some(17) };
+        let errors = validate!(user, {
+            age: [Range(Some(18), Some(120))],
+        });
+        assert_eq!(
+            errors["age"],
+            vec![ValidationError::with_params("range", &[18, 120])]
+        );
+
+        let user = User { age: None };
+        let errors = validate!(user, {
+            age: [Range(Some(18), Some(120))],
+        });
+        assert!(errors.is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use super::{HasLength, ValidationError, Validator};
+
+/// Validates that an optional value is present and not empty.
+///
+/// The other validators skip a `None`, so `Required` goes along with them
+/// to reject a missing value. An empty string or collection counts as
+/// missing.
+///
+/// It only validates an `Option` of a string, `Vec`, `HashMap` or `HashSet`.
+/// A plain value can't be missing, so validate its length instead, e.g. with
+/// `Length(1, 20)`; `Required` can't have impls for plain values anyway, as
+/// through the blanket `Validator<Option<T>>` impl they would accept `None`.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{validate, Length, Required};
+///
+/// struct User {
+///     nickname: Option<String>,
+/// }
+///
+/// let user = User { nickname: None };
+/// let errors = validate!(user, {
+///     nickname: [Required, Length(1, 20)],
+/// });
+///
+/// assert_eq!(errors["nickname"].len(), 1);
+/// ```
+pub struct Required;
+
+fn required<T: HasLength>(value: &Option<T>) -> Option<ValidationError> {
+    match *value {
+        Some(ref value) if value.length() > 0 => None,
+        _ => Some(ValidationError::new("required")),
+    }
+}
+
+// The impls are over concrete types, as a generic one would overlap with the
+// blanket `Validator<Option<T>>` impl that skips `None`.
+macro_rules! impl_required {
+    ($(<$($param:tt),+> $ty:ty),+) => {
+        $(
+            impl<$($param),+> Validator<Option<$ty>> for Required {
+                fn validate(&self, value: &Option<$ty>) -> Option<ValidationError> {
+                    required(value)
+                }
+            }
+        )+
+    };
+}
+
+impl Validator<Option<String>> for Required {
+    fn validate(&self, value: &Option<String>) -> Option<ValidationError> {
+        required(value)
+    }
+}
+
+impl_required!(
+    <'a> &'a str,
+    <'a> Cow<'a, str>,
+    <T> Vec<T>,
+    <K, V> HashMap<K, V>,
+    <T> HashSet<T>
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate, Length};
+
+    #[test]
+    fn test_required_validator() {
+        assert_matches!(Required.validate(&Some("x")), None);
+        assert_matches!(Required.validate(&Some(vec![1])), None);
+
+        for value in &[Some("".to_owned()), None] {
+            let error = Required.validate(value);
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::new("required"));
+            });
+        }
+    }
+
+    #[test]
+    fn test_required_validator_with_length() {
+        struct User {
+            nickname: Option<String>,
+        }
+
+        let cases = vec![
+            (None, vec![ValidationError::new("required")]),
+            (
+                Some("".to_owned()),
+                vec![
+                    ValidationError::new("required"),
+                    ValidationError::with_params("length", &[1, 4]),
+                ],
+            ),
+            (
+                Some("nickname".to_owned()),
+                vec![ValidationError::with_params("length", &[1, 4])],
+            ),
+        ];
+        for (nickname, expected) in cases {
+            let user = User { nickname };
+            let errors = validate!(user, {
+                nickname: [Required, Length(1, 4)],
+            });
+            assert_eq!(errors["nickname"], expected);
+        }
+
+        let user = User {
+            nickname: Some("x".to_owned()),
+        };
+        let errors = validate!(user, {
+            nickname: [Required, Length(1, 4)],
+        });
+        assert!(errors.is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+use super::{PlainValue, ValidationError, Validator};
+
+/// The schemes `UrlValidator::default()` accepts.
+pub const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https"];
+
+/// Validates that a string is an absolute URL with one of the `schemes`.
+#[allow(non_snake_case)]
+pub fn Url(schemes: &'static [&'static str]) -> UrlValidator {
+    UrlValidator(schemes)
+}
+
+pub struct UrlValidator(&'static [&'static str]);
+
+/// Accepts http and https URLs.
+impl Default for UrlValidator {
+    fn default() -> Self {
+        Url(DEFAULT_URL_SCHEMES)
+    }
+}
+
+impl<T> Validator<T> for UrlValidator
+where
+    T: AsRef<str> + PlainValue,
+{
+    fn validate(&self, value: &T) -> Option<ValidationError> {
+        match url::Url::parse(value.as_ref()) {
+            Ok(ref url) if self.0.contains(&url.scheme()) => None,
+            _ => Some(ValidationError::new("url")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+
+    #[test]
+    fn test_url_validator() {
+        let validator = UrlValidator::default();
+        assert_matches!(validator.validate(&"https://a.com"), None);
+        assert_matches!(validator.validate(&"http://a.com/path?q=1"), None);
+
+        for value in &["ftp://x", "not a url", "", "//a.com", "a.com"] {
+            let error = validator.validate(value);
+            assert_matches!(error, Some(err) => {
+                assert_eq!(err, ValidationError::new("url"));
+            });
+        }
+    }
+
+    #[test]
+    fn test_url_validator_with_schemes() {
+        let validator = Url(&["ftp"]);
+        assert_matches!(validator.validate(&"ftp://x"), None);
+        assert_matches!(validator.validate(&"https://a.com"), Some(_));
+    }
+
+    #[test]
+    fn test_url_validator_in_validate_macro() {
+        struct Profile {
+            homepage: Option<String>,
+            avatar_url: String,
+        }
+
+        let profile = Profile {
+            homepage: None,
+            avatar_url: "ftp://x/avatar.png".to_owned(),
+        };
+        let errors = validate!(profile, {
+            homepage: [Url(DEFAULT_URL_SCHEMES)],
+            avatar_url: [Url(DEFAULT_URL_SCHEMES)],
+        });
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors["avatar_url"], vec![ValidationError::new("url")]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use super::{ValidationError, Validator};
+
+/// A value that passed the validator `V` when it was constructed.
+///
+/// `V` is a validator type with a `Default`, usually a unit struct wrapping
+/// the built-in validators.
+///
+/// # Examples
+///
+/// ```rust
+/// use lusion_validator::{Length, Validated, ValidationError, Validator};
+///
+/// #[derive(Default)]
+/// struct UsernameRule;
+///
+/// impl Validator<String> for UsernameRule {
+///     fn validate(&self, value: &String) -> Option<ValidationError> {
+///         Length(1, 20).validate(value)
+///     }
+/// }
+///
+/// type Username = Validated<String, UsernameRule>;
+///
+/// let username = Username::try_new("user".to_owned()).unwrap();
+/// assert_eq!(username.len(), 4);
+/// assert!(Username::try_new("".to_owned()).is_err());
+/// ```
+pub struct Validated<T, V> {
+    value: T,
+    validator: PhantomData<V>,
+}
+
+impl<T, V> Validated<T, V>
+where
+    V: Validator<T> + Default,
+{
+    /// Validates `value`, only wrapping it if it passes.
+    pub fn try_new(value: T) -> Result<Self, ValidationError> {
+        match V::default().validate(&value) {
+            Some(error) => Err(error),
+            None => Ok(Validated {
+                value,
+                validator: PhantomData,
+            }),
+        }
+    }
+}
+
+impl<T, V> Validated<T, V> {
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, V> Deref for Validated<T, V> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Clone, V> Clone for Validated<T, V> {
+    fn clone(&self) -> Self {
+        Validated {
+            value: self.value.clone(),
+            validator: PhantomData,
+        }
+    }
+}
+
+impl<T: PartialEq, V> PartialEq for Validated<T, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: fmt::Debug, V> fmt::Debug for Validated<T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.value, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Length;
+
+    #[derive(Default)]
+    struct UsernameRule;
+
+    impl Validator<String> for UsernameRule {
+        fn validate(&self, value: &String) -> Option<ValidationError> {
+            Length(1, 20).validate(value)
+        }
+    }
+
+    type Username = Validated<String, UsernameRule>;
+
+    #[test]
+    fn test_validated_accepts_valid_value() {
+        let username = Username::try_new("user".to_owned());
+
+        assert_matches!(username, Ok(username) => {
+            assert_eq!(*username, "user");
+            assert_eq!(username.into_inner(), "user".to_owned());
+        });
+    }
+
+    #[test]
+    fn test_validated_rejects_invalid_value() {
+        let username = Username::try_new("a".repeat(21));
+
+        assert_matches!(username, Err(err) => {
+            assert_eq!(err, ValidationError::with_params("length", &[1, 20]));
+        });
+    }
+}
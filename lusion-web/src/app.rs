@@ -0,0 +1,162 @@
+//! Application assembly.
+use std::path::PathBuf;
+use std::time::Duration;
+
+use lusion_db::humans::HumanRepository;
+use lusion_db::prelude::*;
+use lusion_db::users::UserRepository;
+use tide::App;
+
+use crate::endpoints::{health, humans, users};
+use crate::middleware::cache::NoStore;
+use crate::middleware::fs::Static;
+use crate::middleware::limits::Limits;
+use crate::middleware::security::{CookieIdentityPolicy, SecurityMiddleware};
+use crate::route::{Method, RouteExt};
+
+/// Application configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The key signing the auth cookie, at least 32 bytes.
+    ///
+    /// Random by default, so the auth cookies don't outlive the process and
+    /// aren't shared between instances; set it for any real deployment.
+    pub auth_signing_key: Vec<u8>,
+    pub cookie_name: String,
+    pub cookie_path: String,
+    pub cookie_domain: String,
+    pub cookie_secure: bool,
+    /// The auth cookie max age in seconds.
+    pub cookie_max_age: i64,
+    /// The url path the static files are served under.
+    pub static_path: String,
+    /// The directory of the static files.
+    pub static_dir: PathBuf,
+    /// The maximum request body size in bytes.
+    pub max_body: u64,
+    /// The maximum size of the request headers in bytes.
+    pub max_headers: usize,
+    /// The time a request has to be responded to.
+    pub request_timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            auth_signing_key: (0..32).map(|_| rand::random::<u8>()).collect(),
+            cookie_name: "auth-cookie".to_owned(),
+            cookie_path: "/".to_owned(),
+            cookie_domain: "localhost".to_owned(),
+            cookie_secure: true,
+            cookie_max_age: 3600,
+            static_path: "/images".to_owned(),
+            static_dir: PathBuf::from("./images"),
+            max_body: 10 * 1024 * 1024,
+            max_headers: 16 * 1024,
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Builds the application with the standard middleware stack and routes.
+pub struct AppBuilder<Pool> {
+    config: Config,
+    pool: Pool,
+}
+
+impl<Pool> AppBuilder<Pool>
+where
+    Pool: DbPool + Send + Sync + 'static,
+    Pool::Connection: UserRepository + HumanRepository,
+{
+    pub fn new(config: Config, pool: Pool) -> Self {
+        AppBuilder { config, pool }
+    }
+
+    pub fn build(self) -> App<Pool> {
+        let config = self.config;
+        let mut app = App::new(self.pool);
+
+        app.middleware(tide::middleware::RootLogger::new());
+        app.middleware(
+            Limits::new()
+                .max_body(config.max_body)
+                .max_headers(config.max_headers)
+                .request_timeout(config.request_timeout),
+        );
+        app.middleware(SecurityMiddleware::new(
+            CookieIdentityPolicy::new(&config.auth_signing_key)
+                .path(config.cookie_path)
+                .name(config.cookie_name)
+                .domain(config.cookie_domain)
+                .secure(config.cookie_secure)
+                .max_age(config.cookie_max_age),
+        ));
+        app.middleware(NoStore::new().route("/api/users"));
+        app.middleware(Static::new(&config.static_path, config.static_dir));
+
+        app.at("/healthz").get(health::get_health);
+
+        app.at("/api").nest(|api| {
+            api.at("/users").get(users::get_users);
+            api.at("/users").post(users::post_user);
+            api.at("/users").allow(&[Method::GET, Method::POST]);
+            api.at("/users/batch").post(users::post_users_batch);
+            api.at("/users/batch").allow(&[Method::POST]);
+            api.at("/users/import").post(users::post_users_import);
+            api.at("/users/import").allow(&[Method::POST]);
+            api.at("/users/:user_id").get(users::get_user);
+            api.at("/users/:user_id").delete(users::delete_user);
+            api.at("/users/:user_id")
+                .allow(&[Method::GET, Method::DELETE]);
+            api.at("/users/:user_id/password")
+                .put(users::put_user_password);
+            api.at("/users/:user_id/password").allow(&[Method::PUT]);
+            api.at("/humans").post(humans::post_human);
+            api.at("/humans").allow(&[Method::POST]);
+            api.at("/humans/:human_id/friends")
+                .patch(humans::patch_human_friends);
+            api.at("/humans/:human_id/friends").allow(&[Method::PATCH]);
+        });
+
+        app
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn test_app_builder_healthz() {
+        let app = AppBuilder::new(init_config(), init_pool()).build();
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/healthz").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+    }
+
+    #[test]
+    fn test_app_builder_api_routes() {
+        let app = AppBuilder::new(init_config(), init_pool()).build();
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/api/users").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+
+        let req = http::Request::patch("/api/users").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 405);
+    }
+
+    #[test]
+    fn test_config_default_key_is_random() {
+        let (a, b) = (Config::default(), Config::default());
+        assert_eq!(a.auth_signing_key.len(), 32);
+        assert_ne!(a.auth_signing_key, b.auth_signing_key);
+        assert!(a.cookie_secure);
+    }
+}
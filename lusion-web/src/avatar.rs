@@ -0,0 +1,116 @@
+//! Avatar generation.
+use std::sync::Arc;
+
+use tide::Context;
+use uuid::Uuid;
+
+use crate::request::ContextExt;
+
+/// Generates the avatar url of a new user.
+pub trait AvatarStrategy: Send + Sync + 'static {
+    /// Returns the avatar url of the user `user_id`.
+    fn avatar_url(&self, user_id: &Uuid) -> String;
+}
+
+/// The number of bundled avatar images, `1.png` to `20.png`.
+const AVATAR_COUNT: u32 = 20;
+
+/// The url of one of the bundled avatar images picked at random.
+pub fn random_avatar_url() -> String {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    bundled_avatar_url(rng.gen_range(1, AVATAR_COUNT + 1))
+}
+
+/// The url of one of the bundled avatar images picked by hashing `user_id`,
+/// so the same user always gets the same one.
+pub fn deterministic_avatar_url(user_id: &Uuid) -> String {
+    let hash = md5::compute(user_id.as_bytes());
+    let n = (u32::from(hash[0]) << 8) | u32::from(hash[1]);
+    bundled_avatar_url(n % AVATAR_COUNT + 1)
+}
+
+fn bundled_avatar_url(avatar_num: u32) -> String {
+    format!("/api/images/avatars/{}.png", avatar_num)
+}
+
+/// Picks one of the bundled avatar images at random.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Random;
+
+impl AvatarStrategy for Random {
+    fn avatar_url(&self, _: &Uuid) -> String {
+        random_avatar_url()
+    }
+}
+
+/// Picks one of the bundled avatar images by hashing the user id, see
+/// `deterministic_avatar_url`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Deterministic;
+
+impl AvatarStrategy for Deterministic {
+    fn avatar_url(&self, user_id: &Uuid) -> String {
+        deterministic_avatar_url(user_id)
+    }
+}
+
+/// A deterministic identicon from an identicon service at the base url.
+#[derive(Debug, Clone)]
+pub struct Identicon(pub String);
+
+impl AvatarStrategy for Identicon {
+    fn avatar_url(&self, user_id: &Uuid) -> String {
+        format!("{}{:x}.png", self.0, md5::compute(user_id.as_bytes()))
+    }
+}
+
+/// The avatar url of the user `user_id` using the strategy set by
+/// `AvatarMiddleware`, or `deterministic_avatar_url` if there isn't one.
+pub fn avatar_url<AppData>(cx: &Context<AppData>, user_id: &Uuid) -> String {
+    match cx.get_ext::<Arc<dyn AvatarStrategy>>() {
+        Some(strategy) => strategy.avatar_url(user_id),
+        None => deterministic_avatar_url(user_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_avatar_url() {
+        let url = Random.avatar_url(&Uuid::new_v4());
+        assert!(url.starts_with("/api/images/avatars/"));
+        assert!(url.ends_with(".png"));
+    }
+
+    #[test]
+    fn test_deterministic_avatar_url() {
+        use std::collections::HashSet;
+
+        let user_id = Uuid::new_v4();
+        let url = deterministic_avatar_url(&user_id);
+        assert_eq!(url, deterministic_avatar_url(&user_id));
+
+        let urls = (0..500)
+            .map(|i| deterministic_avatar_url(&Uuid::from_fields(i, 0, 0, &[0; 8]).unwrap()))
+            .collect::<HashSet<_>>();
+        let expected = (1..=AVATAR_COUNT)
+            .map(bundled_avatar_url)
+            .collect::<HashSet<_>>();
+        assert_eq!(urls, expected);
+    }
+
+    #[test]
+    fn test_identicon_avatar_url_is_stable() {
+        let identicon = Identicon("https://identicon.example.com/".to_owned());
+        let user_id = Uuid::new_v4();
+        let url = identicon.avatar_url(&user_id);
+
+        assert!(url.starts_with("https://identicon.example.com/"));
+        assert_eq!(url, identicon.avatar_url(&user_id));
+        assert_ne!(url, identicon.avatar_url(&Uuid::new_v4()));
+    }
+}
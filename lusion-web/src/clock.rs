@@ -0,0 +1,72 @@
+//! Clock.
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+
+/// A source of the current time.
+pub trait Clock: 'static + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// A `Clock` reading the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A `Clock` that stands still until it's advanced, for testing
+/// time-dependent behavior.
+///
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct FixedClock(Arc<RwLock<DateTime<Utc>>>);
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        FixedClock(Arc::new(RwLock::new(now)))
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.0.write().unwrap() = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.0.write().unwrap();
+        *now = *now + duration;
+    }
+}
+
+impl Default for FixedClock {
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.read().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_clock() {
+        let start = Utc::now();
+        let clock = FixedClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        let shared = clock.clone();
+        shared.advance(Duration::seconds(30));
+        assert_eq!(clock.now(), start + Duration::seconds(30));
+
+        clock.set(start);
+        assert_eq!(shared.now(), start);
+    }
+}
@@ -0,0 +1,103 @@
+//! Request cookies.
+use cookie::{Cookie, CookieJar};
+use http::header::{self, HeaderMap};
+use tide::Context;
+
+use crate::error::{Result, ResultExt};
+use crate::request::ContextExt;
+
+/// The parsed request cookies, cached in the request extensions.
+struct RequestCookies(CookieJar);
+
+/// An extension to `Context` that provides the request cookies.
+pub trait CookieExt {
+    /// Get the request cookies, parsing the `Cookie` headers on the first
+    /// call.
+    ///
+    /// Cookies that fail to parse are skipped.
+    fn cookies(&mut self) -> Result<&CookieJar>;
+
+    /// Get a request cookie by name.
+    fn cookie(&mut self, name: &str) -> Result<Option<Cookie<'static>>>;
+}
+
+impl<AppData> CookieExt for Context<AppData> {
+    fn cookies(&mut self) -> Result<&CookieJar> {
+        if self.get_ext::<RequestCookies>().is_none() {
+            let jar = parse_cookies(self.headers())?;
+            self.insert_ext(RequestCookies(jar));
+        }
+        Ok(&self.require_ext::<RequestCookies>()?.0)
+    }
+
+    fn cookie(&mut self, name: &str) -> Result<Option<Cookie<'static>>> {
+        Ok(self.cookies()?.get(name).cloned())
+    }
+}
+
+fn parse_cookies(headers: &HeaderMap) -> Result<CookieJar> {
+    let mut jar = CookieJar::new();
+    for value in headers.get_all(header::COOKIE) {
+        let value = value.to_str().user_error("Bad Request")?;
+        for cookie in value.split(';').map(str::trim) {
+            if let Ok(cookie) = Cookie::parse_encoded(cookie.to_owned()) {
+                jar.add_original(cookie);
+            }
+        }
+    }
+    Ok(jar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EndpointResult;
+    use crate::response::{self, StatusCode};
+    use crate::test_helpers::*;
+
+    async fn cookies(mut cx: Context<()>) -> EndpointResult {
+        let names = ["locale", "theme", "missing"];
+        let mut values = Vec::new();
+        for name in names.iter() {
+            values.push(cx.cookie(name)?.map(|cookie| cookie.value().to_owned()));
+        }
+        let count = cx.cookies()?.iter().count();
+
+        Ok(response::json(
+            StatusCode::OK,
+            json!({ "values": values, "count": count }),
+        ))
+    }
+
+    #[test]
+    fn test_cookies() {
+        let mut app = tide::App::new(());
+        app.at("/cookies").get(cookies);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/cookies")
+            .header(header::COOKIE, "locale=zh-CN; theme=dark%20blue; =bad")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["values"], json!(["zh-CN", "dark blue", null]));
+        assert_eq!(body["count"], 2);
+    }
+
+    #[test]
+    fn test_no_cookies() {
+        let mut app = tide::App::new(());
+        app.at("/cookies").get(cookies);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/cookies").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["values"], json!([null, null, null]));
+        assert_eq!(body["count"], 0);
+    }
+}
@@ -0,0 +1,71 @@
+use lusion_db::prelude::*;
+use tide::Context;
+
+use crate::response::{self, Response, StatusCode};
+
+pub async fn get_health<Pool: DbPool>(cx: Context<Pool>) -> Response {
+    let pool = cx.app_data();
+    let state = pool.state();
+
+    match pool.health_check() {
+        Ok(()) => response::json(StatusCode::OK, json!({ "status": "ok", "pool": state })),
+        Err(e) => {
+            log::error!("Health check failed: {}", e);
+            response::json(
+                StatusCode::SERVICE_UNAVAILABLE,
+                json!({ "status": "unavailable", "pool": state }),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use lusion_db::error::{DatabaseErrorKind, DieselError};
+
+    struct BrokenPool;
+
+    impl DbPool for BrokenPool {
+        type Connection = PgConn;
+
+        fn with<F, T>(&self, _f: F) -> Result<T, DbError>
+        where
+            F: FnOnce(&Self::Connection) -> Result<T, DbError>,
+        {
+            Err(DbError::Diesel(DieselError::DatabaseError(
+                DatabaseErrorKind::UnableToSendCommand,
+                Box::new("connection refused".to_owned()),
+            )))
+        }
+    }
+
+    #[test]
+    fn test_get_health_should_be_200() {
+        let mut app = tide::App::new(init_pool());
+        app.at("/healthz").get(get_health);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/healthz").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["status"], "ok");
+        assert!(body["pool"]["connections"].is_u64());
+        assert!(body["pool"]["idle_connections"].is_u64());
+    }
+
+    #[test]
+    fn test_get_health_should_be_503() {
+        let mut app = tide::App::new(BrokenPool);
+        app.at("/healthz").get(get_health);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/healthz").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 503);
+        assert_eq!(res.read_body(), r#"{"pool":null,"status":"unavailable"}"#);
+    }
+}
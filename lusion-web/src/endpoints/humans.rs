@@ -0,0 +1,222 @@
+use lusion_db::humans::{CreateHuman, Human, HumanRepository};
+use lusion_db::prelude::*;
+use tide::Context;
+use uuid::Uuid;
+
+use crate::error::{user_error, EndpointResult, Result, ResultExt};
+use crate::pool::PoolExt;
+use crate::request::body_json_detailed;
+use crate::response::{self, StatusCode};
+
+pub async fn post_human<Pool>(mut cx: Context<Pool>) -> EndpointResult
+where
+    Pool: DbPool,
+    Pool::Connection: HumanRepository,
+{
+    let payload: CreateHuman = await!(body_json_detailed(&mut cx))?;
+    let human = cx.db(|conn| conn.create_human(payload))?;
+
+    Ok(response::json(StatusCode::CREATED, human))
+}
+
+/// A JSON Patch (RFC 6902) operation on the friend ids of a human.
+///
+/// The friend ids are a set without a stable order, so they're addressed
+/// by value rather than by index: `{"op": "add", "path": "/-", "value": id}`
+/// adds a friend and `{"op": "remove", "path": "/<id>"}` removes one.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum FriendPatchOp {
+    Add { path: String, value: Uuid },
+    Remove { path: String },
+}
+
+/// The net friend ids to add and to remove of a patch, a later operation on
+/// the same id overriding an earlier one.
+fn friend_changes(patch: Vec<FriendPatchOp>) -> Result<(Vec<Uuid>, Vec<Uuid>)> {
+    let mut adds = Vec::new();
+    let mut removes = Vec::new();
+    for op in patch {
+        let (id, to, from) = match op {
+            FriendPatchOp::Add { ref path, value } if path == "/-" => {
+                (value, &mut adds, &mut removes)
+            }
+            FriendPatchOp::Add { path, .. } => {
+                return Err(user_error(format!("Unsupported add path: {}", path)));
+            }
+            FriendPatchOp::Remove { path } => {
+                let id = path
+                    .get(1..)
+                    .filter(|_| path.starts_with('/'))
+                    .and_then(|id| id.parse::<Uuid>().ok())
+                    .ok_or_else(|| user_error(format!("Unsupported remove path: {}", path)))?;
+                (id, &mut removes, &mut adds)
+            }
+        };
+        from.retain(|other| *other != id);
+        if !to.contains(&id) {
+            to.push(id);
+        }
+    }
+    Ok((adds, removes))
+}
+
+/// Adds and removes the friends of a human, responding with the resulting
+/// friends, after checking that all of the humans exist.
+fn apply_friend_changes<Conn: HumanRepository>(
+    conn: &Conn,
+    human_id: &Uuid,
+    adds: &[Uuid],
+    removes: &[Uuid],
+) -> std::result::Result<Vec<Human>, DbError> {
+    conn.get_human(human_id)?;
+    for id in adds.iter().chain(removes) {
+        if conn.find_human(id)?.is_none() {
+            return Err(DbError::InvalidInput(format!("Unknown friend id: {}", id)));
+        }
+    }
+    conn.add_friends(human_id, adds)?;
+    conn.remove_friends(human_id, removes)?;
+    conn.find_friends_by_human_id(human_id)
+}
+
+/// Applies a JSON Patch of `add` and `remove` operations to the friends of a
+/// human in one transaction, responding with the resulting friends.
+pub async fn patch_human_friends<Pool>(mut cx: Context<Pool>) -> EndpointResult
+where
+    Pool: DbPool,
+    Pool::Connection: HumanRepository,
+{
+    let human_id: Uuid = cx.param("human_id").user_error("Bad Request")?;
+    let patch: Vec<FriendPatchOp> = await!(body_json_detailed(&mut cx))?;
+    let (adds, removes) = friend_changes(patch)?;
+
+    let friends = cx.db(|conn| apply_friend_changes(conn, &human_id, &adds, &removes))?;
+
+    Ok(response::json(StatusCode::OK, friends))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    fn app() -> tide::App<TestPool<PgPool>> {
+        let pool = init_pool();
+        let mut app = tide::App::new(pool);
+
+        app.at("/humans").post(post_human);
+        app.at("/humans/:human_id/friends")
+            .patch(patch_human_friends);
+
+        app
+    }
+
+    #[test]
+    fn test_post_human_should_be_201() {
+        let mut server = init_service(app());
+        let payload = json!({
+            "name": "Luke",
+            "friend_ids": []
+        });
+        let req = http::Request::post("/humans").json(payload);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 201);
+        assert!(res.read_body().contains("Luke"));
+    }
+
+    #[test]
+    fn test_post_human_with_unknown_friend_should_be_400() {
+        let mut server = init_service(app());
+        let payload = json!({
+            "name": "Luke",
+            "friend_ids": [uuid::Uuid::new_v4()]
+        });
+        let req = http::Request::post("/humans").json(payload);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+        assert_eq!(
+            res.read_body(),
+            r#"{"message":"Referenced resource does not exist"}"#
+        );
+    }
+
+    fn patch(value: serde_json::Value) -> Result<(Vec<Uuid>, Vec<Uuid>)> {
+        friend_changes(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn test_friend_changes() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let changes = patch(json!([
+            { "op": "add", "path": "/-", "value": a },
+            { "op": "remove", "path": format!("/{}", b) },
+            { "op": "add", "path": "/-", "value": b },
+            { "op": "remove", "path": format!("/{}", a) },
+            { "op": "remove", "path": format!("/{}", a) },
+        ]));
+        assert_matches!(changes, Ok((adds, removes)) => {
+            assert_eq!(adds, vec![b]);
+            assert_eq!(removes, vec![a]);
+        });
+
+        assert!(patch(json!([{ "op": "add", "path": "/0", "value": a }])).is_err());
+        assert!(patch(json!([{ "op": "remove", "path": "/0" }])).is_err());
+        assert!(patch(json!([{ "op": "remove", "path": a }])).is_err());
+    }
+
+    #[test]
+    fn test_apply_friend_changes() {
+        let result = init_pool().with(|conn| {
+            let create = |name: &str| {
+                conn.create_human(CreateHuman {
+                    name: name.to_owned(),
+                    friend_ids: vec![],
+                })
+            };
+            let alice = create("alice")?;
+            let bob = create("bob")?;
+            let luke = create("Luke")?;
+            conn.add_friend(&luke.id, &alice.id)?;
+
+            let friends = apply_friend_changes(conn, &luke.id, &[bob.id], &[alice.id])?;
+            let unknown = apply_friend_changes(conn, &luke.id, &[], &[Uuid::new_v4()]);
+            let missing = apply_friend_changes(conn, &Uuid::new_v4(), &[], &[]);
+
+            Ok((friends, bob, unknown, missing))
+        });
+
+        assert_matches!(result, Ok((friends, bob, unknown, missing)) => {
+            assert_eq!(friends, vec![bob]);
+            assert_matches!(unknown, Err(DbError::InvalidInput(ref msg)) => {
+                assert!(msg.starts_with("Unknown friend id: "));
+            });
+            assert_matches!(missing, Err(DbError::NotFound));
+        });
+    }
+
+    #[test]
+    fn test_patch_human_friends_bad_patch_should_be_400() {
+        let mut server = init_service(app());
+        let path = format!("/humans/{}/friends", Uuid::new_v4());
+
+        for payload in vec![
+            json!([{ "op": "replace", "path": "/0", "value": Uuid::new_v4() }]),
+            json!([{ "op": "add", "path": "/0", "value": Uuid::new_v4() }]),
+            json!({ "op": "remove", "path": "/0" }),
+        ] {
+            let req = http::Request::patch(&path).json(payload);
+            let res = call_service(&mut server, req);
+            assert_eq!(res.status(), 400);
+        }
+    }
+
+    #[test]
+    fn test_patch_human_friends_unknown_human_should_be_404() {
+        let mut server = init_service(app());
+        let path = format!("/humans/{}/friends", Uuid::new_v4());
+        let req = http::Request::patch(&path).json(json!([]));
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 404);
+    }
+}
@@ -1,3 +1,5 @@
 //! Web API endpoints
 
+pub mod health;
+pub mod humans;
 pub mod users;
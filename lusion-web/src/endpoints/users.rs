@@ -1,19 +1,80 @@
+use lusion_db::page::Paged;
 use lusion_db::prelude::*;
-use lusion_db::users::{CreateUser, UserRepository};
+use lusion_db::users::{self, CreateUser, User, UserRepository};
+use lusion_validator::{validate, Length, LengthValidator, ValidationErrors};
 use tide::Context;
+use uuid::Uuid;
 
-use crate::error::{EndpointResult, ResultExt};
+use crate::avatar;
+use crate::error::{EndpointResult, ErrorKind, ResultExt};
+use crate::fields::Fields;
+use crate::middleware::limits::BodyLimit;
+use crate::pagination::{self, PageQuery};
+use crate::pool::PoolExt;
+use crate::request::{body_bytes, body_json_detailed, body_json_strict, ContextExt};
 use crate::response::{self, StatusCode};
+use crate::security::{Identity, SecurityExt};
+
+/// The fields of a `User` that can be requested with `?fields=`.
+const USER_FIELDS: &[&str] = &[
+    "id",
+    "username",
+    "nickname",
+    "avatar_url",
+    "created_at",
+    "updated_at",
+];
+
+/// The length of a password, when it's set and when it's changed.
+fn password_length() -> LengthValidator {
+    Length(4, 64)
+}
+
+/// Validates the fields a user is created with, on any struct that has them.
+macro_rules! validate_user {
+    ($user:expr) => {
+        validate!($user, {
+            username: [Length(1, 32).chars()],
+            password: [password_length()],
+            nickname: [Length(1, 32).chars()],
+        })
+    };
+}
+
+/// Validates each of `items`, keying the errors by `items[i].field`.
+fn validate_items<T, F>(items: &[T], validate: F) -> ValidationErrors
+where
+    F: Fn(&T) -> ValidationErrors,
+{
+    let mut errors = ValidationErrors::new();
+    for (i, item) in items.iter().enumerate() {
+        for (field, field_errors) in validate(item) {
+            errors.insert(format!("items[{}].{}", i, field), field_errors);
+        }
+    }
+    errors
+}
 
 pub async fn get_users<Pool>(cx: Context<Pool>) -> EndpointResult
 where
     Pool: DbPool,
     Pool::Connection: UserRepository,
 {
-    let pool = cx.app_data();
-    let users = pool.transaction(|conn| conn.find_users()).db_error()?;
+    let query = PageQuery::from_uri(cx.uri())?;
+    let fields = Fields::from_uri(cx.uri(), USER_FIELDS)?;
+    let paged = cx.db(|conn| match query {
+        Some(query) => conn.find_users_paged(query.page()),
+        None => conn.find_users().map(Paged::from),
+    })?;
+    let res = match fields {
+        Some(fields) => response::json(StatusCode::OK, fields.project(&paged.items)),
+        None => response::json(StatusCode::OK, paged.items),
+    };
 
-    Ok(response::json(StatusCode::OK, users))
+    Ok(match query {
+        Some(query) => pagination::page_headers(res, cx.uri(), query, paged.total),
+        None => res,
+    })
 }
 
 pub async fn get_user<Pool>(cx: Context<Pool>) -> EndpointResult
@@ -22,18 +83,24 @@ where
     Pool::Connection: UserRepository,
 {
     let user_id = cx.param("user_id").user_error("Bad Request")?;
-    let pool = cx.app_data();
-    let user = pool
-        .transaction(|conn| conn.find_user(&user_id))
-        .db_error()?;
+    let user = cx.db(|conn| conn.find_user(&user_id))?;
     let res = match user {
-        Some(user) => response::json(StatusCode::OK, user),
+        Some(user) => response::json_etag(cx.headers(), &user_etag(&user), StatusCode::OK, user),
         None => response::json(StatusCode::NOT_FOUND, json!({ "message": "Not Found" })),
     };
 
     Ok(res)
 }
 
+/// A weak ETag that changes whenever the user is updated.
+fn user_etag(user: &User) -> String {
+    response::weak_etag(format_args!(
+        "{}-{}",
+        user.id.to_simple(),
+        user.updated_at.timestamp_nanos()
+    ))
+}
+
 #[derive(Deserialize)]
 struct PostUser {
     username: String,
@@ -46,27 +113,118 @@ where
     Pool: DbPool,
     Pool::Connection: UserRepository,
 {
-    let payload: PostUser = await!(cx.body_json()).user_error("Bad Request")?;
+    let payload: PostUser = await!(body_json_strict(&mut cx))?;
+    let errors = validate_user!(payload);
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
     let pool = cx.app_data();
     let username = payload.username;
-    let password = bcrypt::hash(&payload.password, bcrypt::DEFAULT_COST)
-        .user_error("password encode error")?;
+    let password = payload.password;
     let nickname = payload.nickname;
-    let avatar_url = random_avatar_url();
+    let id = Uuid::new_v4();
+    let avatar_url = avatar::avatar_url(&cx, &id);
     let user = pool
         .transaction(|conn| {
-            conn.create_user(CreateUser {
-                username,
-                password,
-                nickname,
-                avatar_url,
-            })
+            conn.create_user(
+                CreateUser {
+                    id,
+                    username,
+                    password,
+                    nickname,
+                    avatar_url,
+                },
+                pool.password_hasher(),
+            )
         })
         .db_error()?;
 
     Ok(response::json(StatusCode::CREATED, user))
 }
 
+pub async fn post_users_batch<Pool>(mut cx: Context<Pool>) -> EndpointResult
+where
+    Pool: DbPool,
+    Pool::Connection: UserRepository,
+{
+    let payload: Vec<PostUser> = await!(body_json_strict(&mut cx))?;
+    let errors = validate_items(&payload, |item| validate_user!(item));
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    let pool = cx.app_data();
+    let inputs = payload
+        .into_iter()
+        .map(|item| {
+            let id = Uuid::new_v4();
+            CreateUser {
+                id,
+                avatar_url: avatar::avatar_url(&cx, &id),
+                username: item.username,
+                password: item.password,
+                nickname: item.nickname,
+            }
+        })
+        .collect();
+    let results = pool
+        .with(|conn| Ok(conn.create_users_partial(inputs, pool.password_hasher())))
+        .db_error()?;
+    let items = results
+        .into_iter()
+        .map(|result| match result {
+            Ok(user) => json!({ "status": 201, "user": user }),
+            Err(DbError::Conflict(_)) => json!({ "status": 409, "message": "Conflict" }),
+            Err(e) => {
+                log::error!("Failed to create user: {}", e);
+                json!({ "status": 500, "message": "Internal Server Error" })
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(response::json(StatusCode::MULTI_STATUS, items))
+}
+
+/// The largest CSV body `post_users_import` reads, below any smaller
+/// `Limits::max_body`.
+pub const IMPORT_MAX_BODY: u64 = 4 * 1024 * 1024;
+
+/// Imports users from a CSV body, see `users::read_csv_users`.
+///
+/// Only authenticated callers may import users. Nothing is streamed: the
+/// whole body, up to `IMPORT_MAX_BODY` bytes, and then all of its rows are
+/// held in memory, so every row is validated like `post_user` before any is
+/// inserted. The rows are then inserted in batches, in a single transaction.
+pub async fn post_users_import<Pool>(mut cx: Context<Pool>) -> EndpointResult
+where
+    Pool: DbPool,
+    Pool::Connection: UserRepository,
+{
+    // Without `SecurityMiddleware` the caller is anonymous.
+    if cx.identity().unwrap_or(None).is_none() {
+        return Err(ErrorKind::Unauthorized.into());
+    }
+    let max_body = cx
+        .get_ext::<BodyLimit>()
+        .map_or(IMPORT_MAX_BODY, |limit| limit.0.min(IMPORT_MAX_BODY));
+    cx.insert_ext(BodyLimit(max_body));
+    let body = await!(body_bytes(&mut cx))?;
+    let inputs = users::read_csv_users(&mut &body[..])
+        .collect::<Result<Vec<_>, _>>()
+        .db_error()?;
+    let errors = validate_items(&inputs, |input| validate_user!(input));
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    let hasher = cx.app_data().password_hasher();
+    let mut inputs = inputs.into_iter().map(Ok::<_, DbError>);
+    let imported = cx.db(|conn| conn.import_users(&mut inputs, hasher))?;
+
+    Ok(response::json(
+        StatusCode::CREATED,
+        json!({ "imported": imported }),
+    ))
+}
+
 #[derive(Deserialize)]
 struct PutPassword {
     old_password: String,
@@ -79,65 +237,78 @@ where
     Pool::Connection: UserRepository,
 {
     let user_id = cx.param("user_id").user_error("Bad Request")?;
-    let payload: PutPassword = await!(cx.body_json()).user_error("Bad Request")?;
-    let pool = cx.app_data();
-    let user = pool.with(|conn| conn.find_user(&user_id)).db_error()?;
-
-    let res = match user {
-        None => response::json(StatusCode::NOT_FOUND, json!({ "message": "Not Found" })),
-        Some(user) => {
-            let verified =
-                bcrypt::verify(&payload.old_password, &user.password).user_error("Bad Request")?;
-            if verified {
-                let password = bcrypt::hash(&payload.new_password, bcrypt::DEFAULT_COST)
-                    .user_error("Bad Request")?;
-                let _ = pool
-                    .with(|conn| conn.update_user_password(&user_id, &password))
-                    .db_error()?;
-                response::empty(StatusCode::OK)
-            } else {
-                response::json(
-                    StatusCode::BAD_REQUEST,
-                    json!({ "message": "No match password" }),
-                )
-            }
+    let payload: PutPassword = await!(body_json_detailed(&mut cx))?;
+    let errors = validate!(payload, {
+        new_password: [password_length()],
+    });
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    let hasher = cx.app_data().password_hasher();
+    let verified = cx.db(|conn| {
+        conn.get_user(&user_id)?;
+        if !conn.verify_password(&user_id, &payload.old_password, hasher)? {
+            return Ok(false);
         }
-    };
+        conn.update_user_password(&user_id, &payload.new_password, hasher)?;
+        Ok(true)
+    })?;
+    if !verified {
+        return Err(ErrorKind::UserError("No match password".to_owned()).into());
+    }
 
-    Ok(res)
+    Ok(response::empty(StatusCode::OK))
 }
 
+/// The header confirming that users are deleting their own account.
+pub const CONFIRM_DELETE_HEADER: &str = "X-Confirm-Delete";
+
+/// Deletes a user.
+///
+/// Users deleting their own account must confirm it with a
+/// `X-Confirm-Delete: true` header.
 pub async fn delete_user<Pool>(mut cx: Context<Pool>) -> EndpointResult
 where
     Pool: DbPool,
     Pool::Connection: UserRepository,
 {
-    let user_id = cx.param("user_id").user_error("Bad Request")?;
+    let user_id: Uuid = cx.param("user_id").user_error("Bad Request")?;
+    // Without `SecurityMiddleware` the caller is anonymous.
+    let identity = cx.identity().unwrap_or(None);
+    if identity == Some(Identity::new(user_id.to_string())) {
+        let confirmed = cx
+            .headers()
+            .get(CONFIRM_DELETE_HEADER)
+            .map_or(false, |value| value == "true");
+        if !confirmed {
+            return Err(ErrorKind::ConfirmationRequired.into());
+        }
+    }
     let pool = cx.app_data();
     let _ = pool.with(|conn| conn.delete_user(&user_id)).db_error()?;
 
     Ok(response::empty(StatusCode::NO_CONTENT))
 }
 
-fn random_avatar_url() -> String {
-    use rand::Rng;
-
-    let mut rng = rand::thread_rng();
-    let avatar_num: i32 = rng.gen_range(1, 21);
-    format!("/api/images/avatars/{}.png", avatar_num)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::test_helpers::*;
 
     fn app() -> tide::App<TestPool<PgPool>> {
-        let pool = init_pool();
+        app_with(init_pool())
+    }
+
+    fn app_with<Pool>(pool: Pool) -> tide::App<Pool>
+    where
+        Pool: DbPool + Send + Sync + 'static,
+        Pool::Connection: UserRepository,
+    {
         let mut app = tide::App::new(pool);
 
         app.at("/users").get(get_users);
         app.at("/users").post(post_user);
+        app.at("/users/batch").post(post_users_batch);
         app.at("/users/:user_id").get(get_user);
         app.at("/users/:user_id").delete(delete_user);
         app.at("/users/:user_id/password").put(put_user_password);
@@ -151,7 +322,157 @@ mod tests {
         let req = http::Request::get("/users").to_request();
         let res = call_service(&mut server, req);
         assert_eq!(res.status(), 200);
+        assert!(res.headers().get("X-Total-Count").is_none());
+        assert!(res.headers().get("Link").is_none());
+        assert_eq!(res.read_body(), "[]");
+    }
+
+    #[test]
+    fn test_get_users_paged_should_be_200() {
+        let mut server = init_service(app_with(init_shared_pool()));
+        for i in 0..3 {
+            let payload = json!({
+                "username": format!("pageduser{}", i),
+                "password": "1234",
+                "nickname": "pageduser"
+            });
+            let res = call_service(&mut server, http::Request::post("/users").json(payload));
+            assert_eq!(res.status(), 201);
+        }
+
+        let req = http::Request::get("/users?limit=2&fields=id").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["X-Total-Count"], "3");
+        assert_eq!(
+            res.headers()["Link"],
+            r#"</users?fields=id&offset=2&limit=2>; rel="next""#
+        );
+        let users: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(users.as_array().unwrap().len(), 2);
+
+        let req = http::Request::get("/users").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        let users: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(users.as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_get_users_bad_page_should_be_400() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/users?offset=abc").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+    }
+
+    #[test]
+    fn test_get_users_with_fields() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/users?fields=id,username").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
         assert_eq!(res.read_body(), "[]");
+
+        let req = http::Request::get("/users?fields=id,password").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+        assert_eq!(res.read_body(), r#"{"message":"Unknown field: password"}"#);
+    }
+
+    #[test]
+    fn test_get_users_with_fields_projects_rows() {
+        let mut server = init_service(app_with(init_shared_pool()));
+        let payload = json!({
+            "username": "fieldsuser",
+            "password": "1234",
+            "nickname": "fieldsuser"
+        });
+        let res = call_service(&mut server, http::Request::post("/users").json(payload));
+        assert_eq!(res.status(), 201);
+        let user: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+
+        let req = http::Request::get("/users?fields=id,username").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        let users: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(
+            users,
+            json!([{ "id": user["id"], "username": "fieldsuser" }])
+        );
+    }
+
+    #[test]
+    fn test_user_fields_projection() {
+        let now = chrono::Utc::now();
+        let user = User {
+            id: uuid::Uuid::new_v4(),
+            username: "testuser".to_owned(),
+            password: "secret".to_owned(),
+            nickname: "testname".to_owned(),
+            avatar_url: "".to_owned(),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        };
+        let uri = "/users?fields=id,username".parse().unwrap();
+        let fields = Fields::from_uri(&uri, USER_FIELDS).unwrap().unwrap();
+
+        assert_eq!(
+            fields.project(&[&user]),
+            json!([{ "id": user.id, "username": "testuser" }])
+        );
+        for &field in USER_FIELDS {
+            assert!(serde_json::to_value(&user).unwrap().get(field).is_some());
+        }
+    }
+
+    #[test]
+    fn test_user_etag_changes_after_update() {
+        let now = chrono::Utc::now();
+        let mut user = User {
+            id: uuid::Uuid::new_v4(),
+            username: "testuser".to_owned(),
+            password: "".to_owned(),
+            nickname: "testname".to_owned(),
+            avatar_url: "".to_owned(),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        };
+        let etag = user_etag(&user);
+        assert!(etag.starts_with("W/\""));
+        assert_eq!(etag, user_etag(&user));
+
+        user.updated_at = now + chrono::Duration::seconds(1);
+        assert_ne!(etag, user_etag(&user));
+    }
+
+    #[test]
+    fn test_get_user_if_none_match_should_be_304() {
+        let mut server = init_service(app_with(init_shared_pool()));
+        let payload = json!({
+            "username": "etaguser",
+            "password": "1234",
+            "nickname": "etaguser"
+        });
+        let res = call_service(&mut server, http::Request::post("/users").json(payload));
+        assert_eq!(res.status(), 201);
+        let user: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        let uri = format!("/users/{}", user["id"].as_str().unwrap());
+
+        let res = call_service(&mut server, http::Request::get(uri.as_str()).to_request());
+        assert_eq!(res.status(), 200);
+        let etag = res.headers()[http::header::ETAG].clone();
+        assert!(etag.to_str().unwrap().starts_with("W/\""));
+
+        let req = http::Request::get(uri.as_str())
+            .header(http::header::IF_NONE_MATCH, etag.clone())
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 304);
+        assert_eq!(res.headers()[http::header::ETAG], etag);
+        assert_eq!(res.read_body(), "");
     }
 
     #[test]
@@ -178,6 +499,108 @@ mod tests {
         assert!(body.contains("username"));
         assert!(body.contains("testuser"));
         assert!(body.contains("testname"));
+
+        let user: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let user_id = user["id"].as_str().unwrap().parse().unwrap();
+        assert_eq!(
+            user["avatar_url"],
+            avatar::deterministic_avatar_url(&user_id)
+        );
+    }
+
+    #[test]
+    fn test_post_user_form_body_should_be_415() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/users")
+            .header(http::header::CONTENT_TYPE, "text/plain")
+            .body(http_service::Body::from(
+                "username=testuser&password=1234&nickname=testname",
+            ))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 415);
+    }
+
+    #[test]
+    fn test_post_user_missing_field_should_be_400() {
+        let mut server = init_service(app());
+        let payload = json!({
+            "username": "testuser",
+            "nickname": "testname"
+        });
+        let req = http::Request::post("/users").json(payload);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        let message = body["message"].as_str().unwrap();
+        assert!(message.contains("missing field `password`"));
+    }
+
+    #[test]
+    fn test_post_invalid_user_should_be_400() {
+        let mut server = init_service(app());
+        let payload = json!({
+            "username": "",
+            "password": "12",
+            "nickname": "testname"
+        });
+        let req = http::Request::post("/users").json(payload);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["message"], "Validation failed");
+        assert_eq!(body["errors"]["username"][0]["code"], "length");
+        assert_eq!(body["errors"]["password"][0]["code"], "length");
+        assert!(body["errors"].get("nickname").is_none());
+    }
+
+    #[test]
+    fn test_post_users_batch_should_be_207() {
+        let mut server = init_service(app());
+        let payload = json!([
+            {
+                "username": "batchuser",
+                "password": "1234",
+                "nickname": "first"
+            },
+            {
+                "username": "batchuser",
+                "password": "1234",
+                "nickname": "second"
+            }
+        ]);
+        let req = http::Request::post("/users/batch").json(payload);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 207);
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body[0]["status"], 201);
+        assert_eq!(body[0]["user"]["nickname"], "first");
+        assert_eq!(body[1]["status"], 409);
+    }
+
+    #[test]
+    fn test_post_invalid_users_batch_should_be_400() {
+        let mut server = init_service(app());
+        let payload = json!([
+            {
+                "username": "batchuser",
+                "password": "1234",
+                "nickname": "first"
+            },
+            {
+                "username": "",
+                "password": "12",
+                "nickname": "second"
+            }
+        ]);
+        let req = http::Request::post("/users/batch").json(payload);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["message"], "Validation failed");
+        assert_eq!(body["errors"]["items[1].username"][0]["code"], "length");
+        assert_eq!(body["errors"]["items[1].password"][0]["code"], "length");
+        assert!(body["errors"].get("items[0].username").is_none());
     }
 
     #[test]
@@ -193,6 +616,54 @@ mod tests {
         assert_eq!(res.status(), 404);
     }
 
+    #[test]
+    fn test_put_invalid_user_password_should_be_400() {
+        let mut server = init_service(app());
+        let payload = json!({
+            "old_password": "1234",
+            "new_password": "12"
+        });
+        let req =
+            http::Request::put(format!("/users/{}/password", uuid::Uuid::new_v4())).json(payload);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["message"], "Validation failed");
+        assert_eq!(body["errors"]["new_password"][0]["code"], "length");
+    }
+
+    #[test]
+    fn test_put_user_password() {
+        let mut server = init_service(app_with(init_shared_pool()));
+        let payload = json!({
+            "username": "passworduser",
+            "password": "1234",
+            "nickname": "passworduser"
+        });
+        let res = call_service(&mut server, http::Request::post("/users").json(payload));
+        assert_eq!(res.status(), 201);
+        let user: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        let uri = format!("/users/{}/password", user["id"].as_str().unwrap());
+
+        let payload = json!({
+            "old_password": "4321",
+            "new_password": "5678"
+        });
+        let res = call_service(&mut server, http::Request::put(uri.as_str()).json(payload));
+        assert_eq!(res.status(), 400);
+        assert_eq!(res.read_body(), r#"{"message":"No match password"}"#);
+
+        let payload = json!({
+            "old_password": "1234",
+            "new_password": "5678"
+        });
+        let res = call_service(&mut server, http::Request::put(uri.as_str()).json(&payload));
+        assert_eq!(res.status(), 200);
+
+        let res = call_service(&mut server, http::Request::put(uri.as_str()).json(&payload));
+        assert_eq!(res.status(), 400);
+    }
+
     #[test]
     fn test_delete_user_should_be_204() {
         let mut server = init_service(app());
@@ -200,4 +671,131 @@ mod tests {
         let res = call_service(&mut server, req);
         assert_eq!(res.status(), 204);
     }
+
+    fn authenticated_app(user_id: uuid::Uuid) -> tide::App<TestPool<PgPool>> {
+        use crate::middleware::security::{ApiKeyIdentityPolicy, SecurityMiddleware};
+
+        let mut app = tide::App::new(init_pool());
+        app.middleware(SecurityMiddleware::new(ApiKeyIdentityPolicy::new(
+            move |key: &str| match key {
+                "user-key" => Some(Identity::new(user_id.to_string())),
+                _ => None,
+            },
+        )));
+        app.at("/users/import").post(post_users_import);
+        app.at("/users/:user_id").delete(delete_user);
+
+        app
+    }
+
+    #[test]
+    fn test_delete_self_without_confirmation_should_be_400() {
+        let user_id = uuid::Uuid::new_v4();
+        let mut server = init_service(authenticated_app(user_id));
+        let req = http::Request::delete(format!("/users/{}", user_id))
+            .header("X-API-Key", "user-key")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+        assert_eq!(res.read_body(), r#"{"message":"Confirmation required"}"#);
+
+        let req = http::Request::delete(format!("/users/{}", user_id))
+            .header("X-API-Key", "user-key")
+            .header(CONFIRM_DELETE_HEADER, "false")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+    }
+
+    #[test]
+    fn test_delete_self_with_confirmation_should_be_204() {
+        let user_id = uuid::Uuid::new_v4();
+        let mut server = init_service(authenticated_app(user_id));
+        let req = http::Request::delete(format!("/users/{}", user_id))
+            .header("X-API-Key", "user-key")
+            .header(CONFIRM_DELETE_HEADER, "true")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 204);
+    }
+
+    #[test]
+    fn test_delete_other_user_should_be_204() {
+        let mut server = init_service(authenticated_app(uuid::Uuid::new_v4()));
+        let req = http::Request::delete(format!("/users/{}", uuid::Uuid::new_v4()))
+            .header("X-API-Key", "user-key")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 204);
+    }
+
+    fn import_request(
+        csv: &'static str,
+        api_key: Option<&str>,
+    ) -> http::Request<http_service::Body> {
+        let mut req = http::Request::post("/users/import");
+        req.header("Content-Type", "text/csv");
+        if let Some(api_key) = api_key {
+            req.header("X-API-Key", api_key);
+        }
+        req.body(http_service::Body::from(csv)).unwrap()
+    }
+
+    #[test]
+    fn test_post_users_import_should_be_201() {
+        let mut server = init_service(authenticated_app(uuid::Uuid::new_v4()));
+        let csv = "username,password,nickname,avatar_url\n\
+                   alice,1234,alice,empty.png\n\
+                   bob,4321,bob,empty.png\n";
+        let res = call_service(&mut server, import_request(csv, Some("user-key")));
+        assert_eq!(res.status(), 201);
+        assert_eq!(res.read_body(), r#"{"imported":2}"#);
+    }
+
+    #[test]
+    fn test_post_users_import_anonymous_should_be_401() {
+        let mut server = init_service(authenticated_app(uuid::Uuid::new_v4()));
+        let csv = "username,password,nickname,avatar_url\n\
+                   alice,1234,alice,empty.png\n";
+        let res = call_service(&mut server, import_request(csv, None));
+        assert_eq!(res.status(), 401);
+        assert_eq!(res.read_body(), r#"{"message":"Unauthorized"}"#);
+
+        let res = call_service(&mut server, import_request(csv, Some("unknown-key")));
+        assert_eq!(res.status(), 401);
+    }
+
+    #[test]
+    fn test_post_users_import_too_large_should_be_413() {
+        let mut server = init_service(authenticated_app(uuid::Uuid::new_v4()));
+        let mut req = import_request("", Some("user-key"));
+        *req.body_mut() = http_service::Body::from(vec![b'a'; IMPORT_MAX_BODY as usize + 1]);
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 413);
+    }
+
+    #[test]
+    fn test_post_users_import_malformed_should_be_400() {
+        let mut server = init_service(authenticated_app(uuid::Uuid::new_v4()));
+        let res = call_service(
+            &mut server,
+            import_request("username\nalice\n", Some("user-key")),
+        );
+        assert_eq!(res.status(), 400);
+    }
+
+    #[test]
+    fn test_post_users_import_invalid_should_be_400() {
+        let mut server = init_service(authenticated_app(uuid::Uuid::new_v4()));
+        let csv = "username,password,nickname,avatar_url\n\
+                   alice,1234,alice,empty.png\n\
+                   ,12,bob,empty.png\n";
+        let res = call_service(&mut server, import_request(csv, Some("user-key")));
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["message"], "Validation failed");
+        assert_eq!(body["errors"]["items[1].username"][0]["code"], "length");
+        assert_eq!(body["errors"]["items[1].password"][0]["code"], "length");
+        assert!(body["errors"].get("items[0].username").is_none());
+    }
 }
@@ -2,6 +2,8 @@
 use std::fmt::{self, Display};
 
 use failure::{Backtrace, Context, Fail};
+use lusion_db::error::{DatabaseErrorKind, DbError, DieselError};
+use lusion_validator::ValidationErrors;
 
 use crate::response::{self, IntoResponse, Response, StatusCode};
 
@@ -16,14 +18,75 @@ pub fn user_error<S: Into<String>>(msg: S) -> Error {
     }
 }
 
+pub fn internal_error<S: Into<String>>(msg: S) -> Error {
+    ErrorKind::InternalError(msg.into()).into()
+}
+
 /// A list specifying general categories of application error.
 #[derive(Debug, Clone, Eq, PartialEq, Fail)]
 pub enum ErrorKind {
     #[fail(display = "Database access error")]
     DbError,
 
+    #[fail(display = "Not Found")]
+    NotFound,
+
+    #[fail(display = "Conflict")]
+    Conflict,
+
+    #[fail(display = "Service Unavailable")]
+    Unavailable,
+
+    #[fail(display = "Unsupported Media Type")]
+    UnsupportedMediaType,
+
+    #[fail(display = "Payload Too Large")]
+    PayloadTooLarge,
+
     #[fail(display = "{}", _0)]
     UserError(String),
+
+    #[fail(display = "{}", _0)]
+    InvalidJson(JsonError),
+
+    #[fail(display = "Validation failed")]
+    ValidationFailed(ValidationErrors),
+
+    #[fail(display = "Confirmation required")]
+    ConfirmationRequired,
+
+    #[fail(display = "Unauthorized")]
+    Unauthorized,
+
+    #[fail(display = "Missing request extension")]
+    MissingExtension,
+
+    #[fail(display = "{}", _0)]
+    InternalError(String),
+}
+
+/// A request body that failed to deserialize as JSON.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JsonError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl<'a> From<&'a serde_json::Error> for JsonError {
+    fn from(err: &'a serde_json::Error) -> Self {
+        JsonError {
+            message: err.to_string(),
+            line: err.line(),
+            column: err.column(),
+        }
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
 }
 
 /// Generic error type.
@@ -40,10 +103,49 @@ impl Error {
     pub fn status(&self) -> StatusCode {
         use self::ErrorKind::*;
         match self.kind() {
-            UserError(_) => StatusCode::BAD_REQUEST,
+            UserError(_) | InvalidJson(_) | ValidationFailed(_) | ConfirmationRequired => {
+                StatusCode::BAD_REQUEST
+            }
+            Unauthorized => StatusCode::UNAUTHORIZED,
+            NotFound => StatusCode::NOT_FOUND,
+            Conflict => StatusCode::CONFLICT,
+            Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    /// The message returned to the client.
+    ///
+    /// Server errors get a generic message so their causes aren't leaked.
+    pub fn message(&self) -> String {
+        let status = self.status();
+        if status.is_server_error() {
+            status
+                .canonical_reason()
+                .unwrap_or("Internal Server Error")
+                .to_owned()
+        } else {
+            format!("{}", self.kind())
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error {
+            inner: Context::new(kind),
+        }
+    }
+}
+
+/// Rejects a request with the per-field validation errors, e.g.
+/// `if !errors.is_empty() { return Err(errors.into()); }`.
+impl From<ValidationErrors> for Error {
+    fn from(errors: ValidationErrors) -> Self {
+        ErrorKind::ValidationFailed(errors).into()
+    }
 }
 
 impl Fail for Error {
@@ -65,7 +167,24 @@ impl Display for Error {
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         let status = self.status();
-        let payload = json!({ "message": format!("{}", self.kind()) });
+        if status.is_server_error() {
+            match self.cause() {
+                Some(cause) => log::error!("{}: {}", self, cause),
+                None => log::error!("{}", self),
+            }
+        }
+        let payload = match self.kind() {
+            ErrorKind::InvalidJson(err) => json!({
+                "message": self.message(),
+                "line": err.line,
+                "column": err.column,
+            }),
+            ErrorKind::ValidationFailed(errors) => json!({
+                "message": self.message(),
+                "errors": lusion_validator::to_json(&errors),
+            }),
+            _ => json!({ "message": self.message() }),
+        };
 
         response::json(status, payload)
     }
@@ -90,10 +209,129 @@ where
     }
 
     fn db_error(self) -> Result<T, Error> {
-        self.kind(ErrorKind::DbError)
+        self.map_err(|err| {
+            let kind = match (&err as &Fail).downcast_ref::<DbError>() {
+                Some(err) => db_error_kind(err),
+                None => ErrorKind::DbError,
+            };
+            Error {
+                inner: err.context(kind),
+            }
+        })
     }
 
     fn user_error<S: Into<String>>(self, msg: S) -> Result<T, Error> {
         self.kind(ErrorKind::UserError(msg.into()))
     }
 }
+
+/// The kind of a `DbError`, telling apart the errors caused by the request
+/// from the server errors: a missing row is a 404, a unique violation a 409,
+/// an unavailable pool a 503 and any other server error a 500.
+fn db_error_kind(err: &DbError) -> ErrorKind {
+    match err {
+        DbError::Diesel(DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _)) => {
+            ErrorKind::UserError("Referenced resource does not exist".to_owned())
+        }
+        DbError::NotFound | DbError::Diesel(DieselError::NotFound) => ErrorKind::NotFound,
+        DbError::Pool(_) | DbError::ShuttingDown => ErrorKind::Unavailable,
+        DbError::Conflict(_) => ErrorKind::Conflict,
+        DbError::Import(msg) | DbError::InvalidInput(msg) => ErrorKind::UserError(msg.clone()),
+        _ => ErrorKind::DbError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn test_db_error_response_should_hide_cause() {
+        let cause = DieselError::QueryBuilderError(Box::from("syntax error at secret_table"));
+        let err = Err::<(), _>(DbError::Diesel(cause))
+            .db_error()
+            .unwrap_err();
+        let res = err.into_response();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = res.read_body();
+        assert!(!body.contains("secret_table"));
+        assert_eq!(body, r#"{"message":"Internal Server Error"}"#);
+    }
+
+    #[test]
+    fn test_foreign_key_violation_should_be_400() {
+        let cause = DieselError::DatabaseError(
+            DatabaseErrorKind::ForeignKeyViolation,
+            Box::new("violates foreign key constraint".to_owned()),
+        );
+        let err = Err::<(), _>(DbError::Diesel(cause)).db_error().unwrap_err();
+        let res = err.into_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let body = res.read_body();
+        assert_eq!(body, r#"{"message":"Referenced resource does not exist"}"#);
+    }
+
+    #[test]
+    fn test_not_found_should_be_404() {
+        let err = Err::<(), _>(DbError::NotFound).db_error().unwrap_err();
+        let res = err.into_response();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.read_body(), r#"{"message":"Not Found"}"#);
+    }
+
+    #[test]
+    fn test_conflict_should_be_409() {
+        let err = Err::<(), _>(DbError::Conflict("duplicate key".to_owned()))
+            .db_error()
+            .unwrap_err();
+        let res = err.into_response();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert_eq!(res.read_body(), r#"{"message":"Conflict"}"#);
+    }
+
+    #[test]
+    fn test_user_error_response_should_show_message() {
+        let res = user_error("username is taken").into_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let body = res.read_body();
+        assert_eq!(body, r#"{"message":"username is taken"}"#);
+    }
+
+    #[test]
+    fn test_validation_errors_response_should_be_400() {
+        use lusion_validator::ValidationError;
+
+        let mut errors = ValidationErrors::new();
+        errors.insert(
+            "username".to_owned(),
+            vec![ValidationError::with_params("length", &[1, 32])],
+        );
+        let res = Error::from(errors).into_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "message": "Validation failed",
+                "errors": { "username": [{ "code": "length", "params": [1, 32] }] },
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_response_should_show_position() {
+        let err = serde_json::from_str::<Vec<u32>>("[1, \"2\"]").unwrap_err();
+        let res = Error::from(ErrorKind::InvalidJson(JsonError::from(&err))).into_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["line"], 1);
+        assert_eq!(body["column"], 7);
+        assert!(body["message"].as_str().unwrap().contains("invalid type"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Sparse fieldsets.
+use http::Uri;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{user_error, Result, ResultExt};
+
+/// The fields requested with the `fields` query parameter, e.g.
+/// `?fields=id,username`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fields(Vec<String>);
+
+#[derive(Deserialize)]
+struct FieldsQuery {
+    fields: Option<String>,
+}
+
+impl Fields {
+    /// Parse the requested fields of a request uri, failing for the fields
+    /// that aren't `known`.
+    ///
+    /// Yields `None` when no fields are requested.
+    pub fn from_uri(uri: &Uri, known: &[&str]) -> Result<Option<Self>> {
+        let query: FieldsQuery = match uri.query() {
+            Some(query) => serde_urlencoded::from_str(query).user_error("Bad Request")?,
+            None => return Ok(None),
+        };
+        let fields = query
+            .fields
+            .iter()
+            .flat_map(|fields| fields.split(','))
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(|field| {
+                if known.contains(&field) {
+                    Ok(field.to_owned())
+                } else {
+                    Err(user_error(format!("Unknown field: {}", field)))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if fields.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Fields(fields)))
+        }
+    }
+
+    /// Serialize `t` keeping only the requested fields of its objects, or of
+    /// the objects in it if it's an array.
+    pub fn project<T: Serialize>(&self, t: &T) -> Value {
+        match serde_json::to_value(t).unwrap() {
+            Value::Array(items) => {
+                Value::Array(items.into_iter().map(|v| self.select(v)).collect())
+            }
+            value => self.select(value),
+        }
+    }
+
+    fn select(&self, value: Value) -> Value {
+        match value {
+            Value::Object(mut object) => Value::Object(
+                self.0
+                    .iter()
+                    .filter_map(|field| object.remove(field).map(|v| (field.clone(), v)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: &[&str] = &["id", "username", "nickname"];
+
+    #[test]
+    fn test_fields_from_uri() {
+        let uri = "/users?offset=0&fields=id,%20username,".parse().unwrap();
+        let fields = Fields::from_uri(&uri, KNOWN).unwrap();
+        assert_eq!(
+            fields,
+            Some(Fields(vec!["id".to_owned(), "username".to_owned()]))
+        );
+
+        let uri = "/users?offset=0".parse().unwrap();
+        assert_eq!(Fields::from_uri(&uri, KNOWN).unwrap(), None);
+
+        let uri = "/users?fields=id,password".parse().unwrap();
+        let err = Fields::from_uri(&uri, KNOWN).unwrap_err();
+        assert_eq!(err.message(), "Unknown field: password");
+    }
+
+    #[test]
+    fn test_fields_project() {
+        let fields = Fields(vec!["id".to_owned(), "username".to_owned()]);
+        let users = json!([
+            { "id": 1, "username": "a", "nickname": "A" },
+            { "id": 2, "username": "b", "nickname": "B" },
+        ]);
+
+        assert_eq!(
+            fields.project(&users),
+            json!([{ "id": 1, "username": "a" }, { "id": 2, "username": "b" }])
+        );
+        assert_eq!(
+            fields.project(&users[0]),
+            json!({ "id": 1, "username": "a" })
+        );
+    }
+}
@@ -17,12 +17,21 @@ macro_rules! box_async {
     };
 }
 
+pub mod app;
+pub mod avatar;
+pub mod clock;
+pub mod cookies;
 pub mod endpoints;
 pub mod error;
+pub mod fields;
 pub mod middleware;
+pub mod pagination;
+pub mod pool;
 pub mod request;
 pub mod response;
+pub mod route;
 pub mod security;
+pub mod upload;
 
 #[cfg(test)]
 mod test_helpers;
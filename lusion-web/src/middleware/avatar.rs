@@ -0,0 +1,69 @@
+//! Avatar strategy middleware.
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use tide::middleware::{Middleware, Next};
+use tide::Context;
+
+use crate::avatar::AvatarStrategy;
+use crate::request::ContextExt;
+use crate::response::Response;
+
+/// Makes an `AvatarStrategy` available to the endpoints through
+/// `avatar::avatar_url`.
+pub struct AvatarMiddleware {
+    strategy: Arc<dyn AvatarStrategy>,
+}
+
+impl AvatarMiddleware {
+    pub fn new<T: AvatarStrategy>(strategy: T) -> Self {
+        Self {
+            strategy: Arc::new(strategy),
+        }
+    }
+}
+
+impl<Data: Send + Sync + 'static> Middleware<Data> for AvatarMiddleware {
+    fn handle<'a>(
+        &'a self,
+        mut cx: Context<Data>,
+        next: Next<'a, Data>,
+    ) -> BoxFuture<'a, Response> {
+        cx.insert_ext(self.strategy.clone());
+        next.run(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avatar::{self, Identicon};
+    use crate::response::{self, StatusCode};
+    use crate::test_helpers::*;
+
+    fn identicon() -> Identicon {
+        Identicon("https://identicon.example.com/".to_owned())
+    }
+
+    async fn avatar(cx: Context<()>) -> Response {
+        let user_id = cx.param("user_id").unwrap();
+        response::json(StatusCode::OK, avatar::avatar_url(&cx, &user_id))
+    }
+
+    #[test]
+    fn test_avatar_middleware() {
+        let mut app = tide::App::new(());
+        app.middleware(AvatarMiddleware::new(identicon()));
+        app.at("/avatar/:user_id").get(avatar);
+
+        let mut server = init_service(app);
+        let user_id = uuid::Uuid::new_v4();
+        let req = http::Request::get(format!("/avatar/{}", user_id)).to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.read_body(),
+            format!("\"{}\"", identicon().avatar_url(&user_id))
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Cache control middleware.
+use futures::future::BoxFuture;
+use http::header::{self, HeaderValue};
+use tide::middleware::{Middleware, Next};
+use tide::Context;
+
+use super::path_matches;
+use crate::response::Response;
+
+/// Sets `Cache-Control: no-store` on the responses of the routes under the
+/// given paths, so user data isn't kept by caches along the way.
+#[derive(Debug, Default)]
+pub struct NoStore {
+    routes: Vec<String>,
+}
+
+impl NoStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `path` and the paths under it as not to be stored.
+    pub fn route<S: Into<String>>(mut self, path: S) -> Self {
+        let path = path.into();
+        self.routes.push(path.trim_end_matches('/').to_owned());
+        self
+    }
+
+    fn is_sensitive(&self, path: &str) -> bool {
+        self.routes.iter().any(|prefix| path_matches(prefix, path))
+    }
+}
+
+impl<Data: Send + Sync + 'static> Middleware<Data> for NoStore {
+    fn handle<'a>(&'a self, cx: Context<Data>, next: Next<'a, Data>) -> BoxFuture<'a, Response> {
+        let no_store = self.is_sensitive(cx.uri().path());
+        box_async! {
+            let mut res = await!(next.run(cx));
+            if no_store {
+                res.headers_mut()
+                    .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+            }
+            res
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::fs::Static;
+    use crate::response::{self, StatusCode};
+    use crate::test_helpers::*;
+
+    async fn users(_cx: Context<()>) -> Response {
+        response::json(StatusCode::OK, json!([]))
+    }
+
+    fn app() -> tide::App<()> {
+        let mut app = tide::App::new(());
+        app.middleware(NoStore::new().route("/api/users"));
+        app.middleware(Static::new("/static", "./tests/resources"));
+        app.at("/api/users").get(users);
+        app.at("/api/users/:user_id").get(users);
+        app.at("/api/usersx").get(users);
+
+        app
+    }
+
+    #[test]
+    fn test_no_store_middleware() {
+        let mut server = init_service(app());
+        for path in &["/api/users", "/api/users/1"] {
+            let req = http::Request::get(*path).to_request();
+            let res = call_service(&mut server, req);
+            assert_eq!(res.status(), 200);
+            assert_eq!(res.headers()[header::CACHE_CONTROL], "no-store");
+        }
+
+        for path in &["/static/a.txt", "/api/usersx"] {
+            let req = http::Request::get(*path).to_request();
+            let res = call_service(&mut server, req);
+            assert_eq!(res.status(), 200);
+            assert!(res.headers().get(header::CACHE_CONTROL).is_none());
+        }
+    }
+}
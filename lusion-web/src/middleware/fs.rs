@@ -8,6 +8,7 @@ use std::pin::Pin;
 
 use bytes::Bytes;
 use futures::{future::BoxFuture, stream::Stream, task::Context, Poll};
+use http::header::{self, HeaderMap, HeaderValue};
 use tide::middleware::{Middleware, Next};
 
 use crate::response::{self, Response};
@@ -16,6 +17,8 @@ pub struct NamedFile {
     path: PathBuf,
     file: File,
     md: Metadata,
+    content_type: String,
+    content_encoding: Option<&'static str>,
 }
 
 impl NamedFile {
@@ -23,7 +26,28 @@ impl NamedFile {
         let path = path.as_ref().to_path_buf();
         let file = File::open(&path)?;
         let md = file.metadata()?;
-        Ok(NamedFile { path, file, md })
+        let content_type = mime_guess::from_path(&path)
+            .first_or_octet_stream()
+            .to_string();
+        Ok(NamedFile {
+            path,
+            file,
+            md,
+            content_type,
+            content_encoding: None,
+        })
+    }
+
+    /// Open the precompressed `<path>.gz` sibling of the file at `path`,
+    /// served with the content type of the original file.
+    pub fn open_gzip<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content_type = mime_guess::from_path(&path)
+            .first_or_octet_stream()
+            .to_string();
+        let mut file = NamedFile::open(gzip_path(path.as_ref()))?;
+        file.content_type = content_type;
+        file.content_encoding = Some("gzip");
+        Ok(file)
     }
 
     pub fn path(&self) -> &Path {
@@ -37,7 +61,18 @@ impl NamedFile {
             file: self.file,
             counter: 0,
         };
-        response::stream(http::StatusCode::OK, chunk)
+        let mut resp = response::stream(http::StatusCode::OK, chunk);
+        let headers = resp.headers_mut();
+        if let Ok(content_type) = HeaderValue::from_str(&self.content_type) {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        }
+        if let Some(content_encoding) = self.content_encoding {
+            headers.insert(
+                header::CONTENT_ENCODING,
+                HeaderValue::from_static(content_encoding),
+            );
+        }
+        resp
     }
 }
 
@@ -83,6 +118,7 @@ impl Unpin for ChunkedReadFile {}
 pub struct Static {
     path: String,
     directory: PathBuf,
+    fallthrough: bool,
 }
 
 impl Static {
@@ -90,15 +126,38 @@ impl Static {
         Self {
             path: path.to_owned(),
             directory: dir.into(),
+            fallthrough: false,
         }
     }
 
-    fn read_file(&self, path: &str) -> Result<Option<NamedFile>> {
+    /// Passes the requests for missing files on to the next handler instead
+    /// of responding with 404, e.g. to serve a single page app's `index.html`
+    /// from a catch-all route.
+    pub fn fallthrough(mut self, fallthrough: bool) -> Self {
+        self.fallthrough = fallthrough;
+        self
+    }
+
+    /// Read the file at `path`, preferring its precompressed `.gz` sibling
+    /// when the client accepts gzip.
+    fn read_file(&self, path: &str, gzip: bool) -> Result<Option<Response>> {
         let buf = self.get_path_buf(path)?;
         let file_path = self.directory.join(&buf);
 
         if file_path.exists() && file_path.is_file() {
-            return Ok(Some(NamedFile::open(file_path)?));
+            if !gzip_path(&file_path).is_file() {
+                return Ok(Some(NamedFile::open(file_path)?.into_response()));
+            }
+
+            let file = if gzip {
+                NamedFile::open_gzip(file_path)?
+            } else {
+                NamedFile::open(file_path)?
+            };
+            let mut resp = file.into_response();
+            resp.headers_mut()
+                .insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+            return Ok(Some(resp));
         }
 
         Ok(None)
@@ -130,11 +189,12 @@ impl<Data: Send + Sync + 'static> Middleware<Data> for Static {
             let path = cx.uri().path();
             if path.starts_with(&self.path) {
                 let file_path = &path[self.path.len()..];
+                let gzip = accepts_gzip(cx.headers());
 
-                let res = match self.read_file(&file_path) {
-                    Ok(file) => file
-                        .map(|file| file.into_response())
-                        .unwrap_or_else(|| response::empty(http::StatusCode::NOT_FOUND)),
+                let res = match self.read_file(&file_path, gzip) {
+                    Ok(Some(res)) => res,
+                    Ok(None) if self.fallthrough => return await!(next.run(cx)),
+                    Ok(None) => response::empty(http::StatusCode::NOT_FOUND),
                     Err(e) => {
                         log::debug!("Failed to read file: {}", e);
                         response::empty(http::StatusCode::INTERNAL_SERVER_ERROR)
@@ -149,16 +209,36 @@ impl<Data: Send + Sync + 'static> Middleware<Data> for Static {
     }
 }
 
+fn gzip_path(path: &Path) -> PathBuf {
+    let mut gz_path = path.as_os_str().to_os_string();
+    gz_path.push(".gz");
+    PathBuf::from(gz_path)
+}
+
+/// Whether the `Accept-Encoding` headers allow a gzip response.
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|hv| hv.to_str().ok())
+        .flat_map(|s| s.split(','))
+        .any(|coding| {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or("");
+            let refused = params.any(|param| {
+                param.starts_with("q=") && param[2..].parse::<f32>().ok() == Some(0.0)
+            });
+            name.eq_ignore_ascii_case("gzip") && !refused
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::test_helpers::*;
 
     fn app() -> tide::App<()> {
-        let mut app = tide::App::new(());
-        app.middleware(Static::new("/static", "./tests/resources"));
-
-        app
+        init_app_with_static(())
     }
 
     #[test]
@@ -175,4 +255,70 @@ mod tests {
         assert_eq!(res.read_body(), "bbb\n");
     }
 
+    #[test]
+    fn test_static_middleware_precompressed() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/static/c.txt")
+            .header(header::ACCEPT_ENCODING, "deflate, gzip;q=0.8")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.headers().get(header::CONTENT_ENCODING),
+            Some(&HeaderValue::from_static("gzip"))
+        );
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("text/plain"))
+        );
+        let gz = std::fs::read("./tests/resources/c.txt.gz").unwrap();
+        assert_eq!(res.read_bytes(), gz);
+
+        let req = http::Request::get("/static/c.txt").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(res.read_body(), "ccc\n");
+
+        let req = http::Request::get("/static/c.txt")
+            .header(header::ACCEPT_ENCODING, "gzip;q=0")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(res.read_body(), "ccc\n");
+    }
+
+    async fn index(_cx: tide::Context<()>) -> Response {
+        response::html(http::StatusCode::OK, "index")
+    }
+
+    #[test]
+    fn test_static_middleware_fallthrough() {
+        let mut app = tide::App::new(());
+        app.middleware(Static::new("/static", "./tests/resources").fallthrough(true));
+        app.at("/static/*path").get(index);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/static/a.txt").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "aaa\n");
+
+        let req = http::Request::get("/static/missing.txt").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "index");
+    }
+
+    #[test]
+    fn test_static_middleware_missing_file() {
+        let mut app = app();
+        app.at("/static/*path").get(index);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/static/missing.txt").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 404);
+    }
 }
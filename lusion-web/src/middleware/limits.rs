@@ -0,0 +1,235 @@
+//! Request limits middleware.
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use http::header::{self, HeaderMap};
+use tide::middleware::{Middleware, Next};
+use tide::Context;
+
+use super::timeout::Timeout;
+use crate::request::ContextExt;
+use crate::response::{self, Response, StatusCode};
+
+/// Applies the body size, header size and request time limits in one place.
+///
+/// A request is answered with:
+///
+/// * `413 Payload Too Large` when its `Content-Length` exceeds `max_body`,
+///   or once more than `max_body` bytes of its body are read with
+///   `request::body_bytes`, e.g. of a chunked body. Endpoints streaming the
+///   body themselves should still bound what they read.
+/// * `431 Request Header Fields Too Large` when its header names and values
+///   add up to more than `max_headers` bytes.
+/// * `503 Service Unavailable` when it takes longer than `request_timeout`,
+///   see `Timeout`.
+///
+/// Every limit is off until it's set.
+#[derive(Default)]
+pub struct Limits {
+    max_body: Option<u64>,
+    max_headers: Option<usize>,
+    timeout: Option<Timeout>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the request body to `bytes`.
+    pub fn max_body(mut self, bytes: u64) -> Self {
+        self.max_body = Some(bytes);
+        self
+    }
+
+    /// Limits the request headers to `bytes`.
+    pub fn max_headers(mut self, bytes: usize) -> Self {
+        self.max_headers = Some(bytes);
+        self
+    }
+
+    /// Limits the time to respond to `timeout`.
+    pub fn request_timeout(self, timeout: Duration) -> Self {
+        self.timeout(Timeout::new(timeout))
+    }
+
+    /// Limits the time to respond with a `Timeout`, e.g. one with longer
+    /// timeouts for some routes.
+    pub fn timeout(mut self, timeout: Timeout) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The response rejecting a request with the given headers, if any.
+    fn check(&self, headers: &HeaderMap) -> Option<Response> {
+        if let Some(max_headers) = self.max_headers {
+            if headers_size(headers) > max_headers {
+                return Some(response::json(
+                    StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                    json!({ "message": "Request Header Fields Too Large" }),
+                ));
+            }
+        }
+        if let Some(max_body) = self.max_body {
+            if content_length(headers).map_or(false, |length| length > max_body) {
+                return Some(response::json(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    json!({ "message": "Payload Too Large" }),
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// The `max_body` of `Limits`, for the body readers to enforce.
+pub(crate) struct BodyLimit(pub u64);
+
+fn headers_size(headers: &HeaderMap) -> usize {
+    headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len())
+        .sum()
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|hv| hv.to_str().ok())
+        .and_then(|s| s.parse().ok())
+}
+
+impl<Data: Send + Sync + 'static> Middleware<Data> for Limits {
+    fn handle<'a>(
+        &'a self,
+        mut cx: Context<Data>,
+        next: Next<'a, Data>,
+    ) -> BoxFuture<'a, Response> {
+        if let Some(res) = self.check(cx.headers()) {
+            return box_async! { res };
+        }
+        if let Some(max_body) = self.max_body {
+            cx.insert_ext(BodyLimit(max_body));
+        }
+        match self.timeout {
+            Some(ref timeout) => timeout.handle(cx, next),
+            None => next.run(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use futures::stream;
+    use futures_timer::Delay;
+
+    use crate::error::EndpointResult;
+    use crate::request::body_bytes;
+    use crate::test_helpers::*;
+
+    async fn echo(mut cx: Context<()>) -> EndpointResult {
+        let body = await!(body_bytes(&mut cx))?;
+        Ok(response::html(StatusCode::OK, body))
+    }
+
+    async fn slow(_cx: Context<()>) -> Response {
+        let _ = await!(Delay::new(Duration::from_millis(200)));
+        response::empty(StatusCode::OK)
+    }
+
+    fn app() -> tide::App<()> {
+        let mut app = tide::App::new(());
+        app.middleware(
+            Limits::new()
+                .max_body(8)
+                .max_headers(256)
+                .request_timeout(Duration::from_millis(50)),
+        );
+        app.at("/echo").post(echo);
+        app.at("/slow").get(slow);
+        app
+    }
+
+    #[test]
+    fn test_limits_within() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/echo")
+            .header(header::CONTENT_LENGTH, "5")
+            .body(http_service::Body::from("hello"))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "hello");
+    }
+
+    #[test]
+    fn test_limits_max_body() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/echo")
+            .header(header::CONTENT_LENGTH, "11")
+            .body(http_service::Body::from("hello world"))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 413);
+    }
+
+    fn chunked(chunks: &[&'static str]) -> http_service::Body {
+        let chunks = chunks
+            .iter()
+            .map(|&chunk| Ok::<_, std::io::Error>(Bytes::from(chunk)))
+            .collect::<Vec<_>>();
+        http_service::Body::from_stream(stream::iter(chunks))
+    }
+
+    #[test]
+    fn test_limits_max_body_chunked() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/echo")
+            .body(chunked(&["hell", "o"]))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "hello");
+
+        let req = http::Request::post("/echo")
+            .body(chunked(&["hello", " ", "world"]))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 413);
+        assert_eq!(res.read_body(), r#"{"message":"Payload Too Large"}"#);
+    }
+
+    #[test]
+    fn test_limits_max_headers() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/echo")
+            .header("X-Padding", "x".repeat(256))
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 431);
+    }
+
+    #[test]
+    fn test_limits_request_timeout() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/slow").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 503);
+    }
+
+    #[test]
+    fn test_limits_off_by_default() {
+        let mut app = tide::App::new(());
+        app.middleware(Limits::new());
+        app.at("/slow").get(slow);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/slow")
+            .header("X-Padding", "x".repeat(256))
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+    }
+}
@@ -1,3 +1,15 @@
 //! Middlewares.
+pub mod avatar;
+pub mod cache;
 pub mod fs;
+pub mod limits;
 pub mod security;
+pub mod timeout;
+#[cfg(feature = "trace")]
+pub mod trace;
+
+/// Whether `path` is `prefix` or one of the paths under it.
+fn path_matches(prefix: &str, path: &str) -> bool {
+    path.starts_with(prefix)
+        && (path.len() == prefix.len() || path[prefix.len()..].starts_with('/'))
+}
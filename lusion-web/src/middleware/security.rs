@@ -1,4 +1,6 @@
 //! Middleware-based security context.
+use std::sync::Arc;
+
 use cookie::{Cookie, CookieJar, Key};
 use futures::future::BoxFuture;
 use http::header::{self, HeaderValue};
@@ -7,8 +9,9 @@ use tide::middleware::{Middleware, Next};
 use tide::Context;
 use time::Duration;
 
-use crate::request::Request;
-use crate::response::Response;
+use crate::clock::{Clock, SystemClock};
+use crate::request::{ContextExt, Request};
+use crate::response::{self, Response, StatusCode};
 use crate::security::{Identity, SecurityContext};
 
 pub struct SecurityMiddleware {
@@ -37,15 +40,24 @@ impl<Data: Send + Sync + 'static> Middleware<Data> for SecurityMiddleware {
         mut cx: Context<Data>,
         next: Next<'a, Data>,
     ) -> BoxFuture<'a, Response> {
-        let identity = self.policy.from_request(cx.request()).unwrap();
+        // an unreadable identity, e.g. a malformed cookie, is anonymous
+        let identity = self.policy.from_request(cx.request()).unwrap_or_else(|e| {
+            log::warn!("Failed to load identity: {}", e);
+            None
+        });
         let sc = SecurityContext::new(identity);
         box_async! {
-            cx.extensions_mut().insert(sc.clone());
+            cx.insert_ext(sc.clone());
 
             let resp = await!(next.run(cx));
 
             if sc.is_changed() {
-                self.policy.write_response(sc.identity(), resp).unwrap()
+                self.policy
+                    .write_response(sc.identity(), resp)
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to store identity: {}", e);
+                        response::empty(StatusCode::INTERNAL_SERVER_ERROR)
+                    })
             } else {
                 resp
             }
@@ -72,6 +84,24 @@ pub struct CookieIdentityPolicy {
     domain: Option<String>,
     secure: bool,
     max_age: Option<Duration>,
+    clock: Arc<dyn Clock>,
+}
+
+/// The value stored in the identity cookie.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredIdentity {
+    Current(CookieIdentity),
+    /// The bare identity stored by earlier versions, which never expires.
+    Legacy(Identity),
+}
+
+#[derive(Deserialize, Serialize)]
+struct CookieIdentity {
+    identity: Identity,
+    /// Unix timestamp after which the identity is no longer valid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<i64>,
 }
 
 impl CookieIdentityPolicy {
@@ -110,6 +140,12 @@ impl CookieIdentityPolicy {
         self.max_age = Some(value);
         self
     }
+
+    /// Set the `Clock` used to expire identities, defaults to `SystemClock`.
+    pub fn clock<C: Clock>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
 }
 
 impl Default for CookieIdentityPolicy {
@@ -121,6 +157,7 @@ impl Default for CookieIdentityPolicy {
             domain: None,
             secure: false,
             max_age: None,
+            clock: Arc::new(SystemClock),
         }
     }
 }
@@ -144,10 +181,17 @@ impl SecurityIdentityPolicy for CookieIdentityPolicy {
         }
 
         if let Some(auth_cookie) = jar.private(&self.key).get(&self.name) {
-            let identity = serde_json::from_str(auth_cookie.value())
+            let value: StoredIdentity = serde_json::from_str(auth_cookie.value())
                 .map_err(|e| StringError(format!("Failed to deserialize: {}", e)))?;
 
-            Ok(Some(identity))
+            match value {
+                StoredIdentity::Current(CookieIdentity {
+                    expires: Some(expires),
+                    ..
+                }) if self.clock.now().timestamp() >= expires => Ok(None),
+                StoredIdentity::Current(value) => Ok(Some(value.identity)),
+                StoredIdentity::Legacy(identity) => Ok(Some(identity)),
+            }
         } else {
             Ok(None)
         }
@@ -173,7 +217,10 @@ impl SecurityIdentityPolicy for CookieIdentityPolicy {
         }
 
         if let Some(identity) = identity {
-            let value = serde_json::to_string(&identity)
+            let expires = self
+                .max_age
+                .map(|max_age| self.clock.now().timestamp() + max_age.num_seconds());
+            let value = serde_json::to_string(&CookieIdentity { identity, expires })
                 .map_err(|e| StringError(format!("Failed to serialize: {}", e)))?;
             cookie.set_value(value);
 
@@ -200,10 +247,67 @@ impl SecurityIdentityPolicy for CookieIdentityPolicy {
     }
 }
 
+/// Resolves an API key into an `Identity`.
+pub trait ApiKeyResolver: 'static + Send + Sync {
+    fn resolve(&self, key: &str) -> Option<Identity>;
+}
+
+impl<F> ApiKeyResolver for F
+where
+    F: Fn(&str) -> Option<Identity> + Send + Sync + 'static,
+{
+    fn resolve(&self, key: &str) -> Option<Identity> {
+        (self)(key)
+    }
+}
+
+pub struct ApiKeyIdentityPolicy {
+    header: String,
+    resolver: Box<dyn ApiKeyResolver>,
+}
+
+impl ApiKeyIdentityPolicy {
+    pub fn new<R: ApiKeyResolver>(resolver: R) -> Self {
+        Self {
+            header: "X-API-Key".to_owned(),
+            resolver: Box::new(resolver),
+        }
+    }
+
+    pub fn header<S: Into<String>>(mut self, value: S) -> Self {
+        self.header = value.into();
+        self
+    }
+}
+
+impl SecurityIdentityPolicy for ApiKeyIdentityPolicy {
+    fn from_request(&self, req: &Request) -> Result<Option<Identity>, StringError> {
+        match req.headers().get(self.header.as_str()) {
+            Some(hv) => {
+                let key = hv
+                    .to_str()
+                    .map_err(|e| StringError(format!("Failed to parse header value: {}", e)))?;
+
+                Ok(self.resolver.resolve(key.trim()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_response(
+        &self,
+        _identity: Option<Identity>,
+        resp: Response,
+    ) -> Result<Response, StringError> {
+        Ok(resp)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::response::{self, StatusCode};
+    use crate::clock::FixedClock;
     use crate::security::SecurityExt;
     use crate::test_helpers::*;
 
@@ -219,6 +323,10 @@ mod tests {
         ctx.remember(Identity::new("user")).unwrap();
     }
 
+    async fn remember_admin(mut ctx: Context<()>) {
+        ctx.remember(Identity::new("admin")).unwrap();
+    }
+
     async fn forget(mut ctx: Context<()>) {
         ctx.forget().unwrap();
     }
@@ -231,10 +339,35 @@ mod tests {
 
         app.at("/get").get(retrieve);
         app.at("/remember").get(remember);
+        app.at("/remember/admin").get(remember_admin);
         app.at("/forget").get(forget);
         app
     }
 
+    fn expiring_app(clock: FixedClock) -> tide::App<()> {
+        let mut app = tide::App::new(());
+        app.middleware(SecurityMiddleware::new(
+            CookieIdentityPolicy::new(&[0; 32]).max_age(60).clock(clock),
+        ));
+
+        app.at("/get").get(retrieve);
+        app.at("/remember").get(remember);
+        app
+    }
+
+    fn api_key_app() -> tide::App<()> {
+        let mut app = tide::App::new(());
+        app.middleware(SecurityMiddleware::new(ApiKeyIdentityPolicy::new(
+            |key: &str| match key {
+                "secret-key" => Some(Identity::new("service")),
+                _ => None,
+            },
+        )));
+
+        app.at("/get").get(retrieve);
+        app
+    }
+
     fn app() -> tide::App<()> {
         let mut app = tide::App::new(());
         app.middleware(SecurityMiddleware::default());
@@ -295,6 +428,95 @@ mod tests {
         assert_eq!(res.read_body(), "\"user\"");
     }
 
+    #[test]
+    fn test_init_app_with_security_should_match_hand_built_app() {
+        let mut helper_app = init_app_with_security(());
+        helper_app.at("/get").get(retrieve);
+        helper_app.at("/remember").get(remember);
+        helper_app.at("/forget").get(forget);
+
+        for app in vec![helper_app, app()] {
+            let mut server = init_service(app);
+
+            let req = http::Request::get("/remember").to_request();
+            let res = call_service(&mut server, req);
+            assert_eq!(res.status(), 200);
+            let auth_cookie = res.get_cookie("tide-auth").unwrap();
+
+            let req = http::Request::get("/get").cookie(&auth_cookie).to_request();
+            let res = call_service(&mut server, req);
+            assert_eq!(res.status(), 200);
+            assert_eq!(res.read_body(), "\"user\"");
+        }
+    }
+
+    fn cookie_identity(cookie: &Cookie<'static>) -> CookieIdentity {
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie.clone());
+        let cookie = jar
+            .private(&Key::from_master(&[0; 32]))
+            .get(cookie.name())
+            .unwrap();
+        serde_json::from_str(cookie.value()).unwrap()
+    }
+
+    /// An identity cookie holding `value` as written by the policy.
+    fn private_cookie(value: &str) -> Cookie<'static> {
+        let mut jar = CookieJar::new();
+        jar.private(&Key::from_master(&[0; 32]))
+            .add(Cookie::new("tide-auth", value.to_owned()));
+        jar.delta().next().unwrap().clone()
+    }
+
+    #[test]
+    fn test_legacy_cookie_should_be_remembered() {
+        let mut server = init_service(named_cookie_app("tide-auth"));
+
+        let legacy = private_cookie("\"user\"");
+        let req = http::Request::get("/get").cookie(&legacy).to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "\"user\"");
+    }
+
+    #[test]
+    fn test_malformed_cookie_should_be_anonymous() {
+        let mut server = init_service(named_cookie_app("tide-auth"));
+
+        let malformed = private_cookie("{\"unknown\": 1}");
+        let req = http::Request::get("/get").cookie(&malformed).to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "\"anonymous\"");
+    }
+
+    #[test]
+    fn test_remember_should_not_reuse_preset_cookie() {
+        let mut server = init_service(named_cookie_app("tide-auth"));
+
+        // a valid cookie of the attacker's own session, planted on the victim
+        let req = http::Request::get("/remember").to_request();
+        let res = call_service(&mut server, req);
+        let preset = res.get_cookie("tide-auth").unwrap();
+
+        let req = http::Request::get("/remember/admin")
+            .cookie(&preset)
+            .to_request();
+        let res = call_service(&mut server, req);
+        let issued = res.get_cookie("tide-auth").unwrap();
+        assert_ne!(issued.value(), preset.value());
+        assert_eq!(cookie_identity(&issued).identity, Identity::new("admin"));
+
+        // the planted cookie didn't become the authenticated session
+        let req = http::Request::get("/get").cookie(&preset).to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.read_body(), "\"user\"");
+
+        let req = http::Request::get("/get").cookie(&issued).to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.read_body(), "\"admin\"");
+    }
+
     #[test]
     fn test_set_cookie_identity_policy_cookie_name() {
         let mut server = init_service(named_cookie_app("test-cookie123"));
@@ -307,4 +529,50 @@ mod tests {
         let auth_cookie = res.get_cookie("test-cookie123");
         assert!(auth_cookie.is_some());
     }
+
+    #[test]
+    fn test_expired_identity_should_be_anonymous() {
+        let clock = FixedClock::default();
+        let mut server = init_service(expiring_app(clock.clone()));
+
+        let req = http::Request::get("/remember").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+
+        let auth_cookie = res.get_cookie("tide-auth").unwrap();
+
+        clock.advance(chrono::Duration::seconds(59));
+        let req = http::Request::get("/get").cookie(&auth_cookie).to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.read_body(), "\"user\"");
+
+        clock.advance(chrono::Duration::seconds(1));
+        let req = http::Request::get("/get").cookie(&auth_cookie).to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.read_body(), "\"anonymous\"");
+    }
+
+    #[test]
+    fn test_api_key_identity_policy_known_key() {
+        let mut server = init_service(api_key_app());
+
+        let req = http::Request::get("/get")
+            .header("X-API-Key", "secret-key")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "\"service\"");
+    }
+
+    #[test]
+    fn test_api_key_identity_policy_unknown_key() {
+        let mut server = init_service(api_key_app());
+
+        let req = http::Request::get("/get")
+            .header("X-API-Key", "unknown-key")
+            .to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "\"anonymous\"");
+    }
 }
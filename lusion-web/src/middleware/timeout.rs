@@ -0,0 +1,118 @@
+//! Request timeout middleware.
+use std::time::Duration;
+
+use futures::future::{self, BoxFuture, Either};
+use futures_timer::Delay;
+use tide::middleware::{Middleware, Next};
+use tide::Context;
+
+use super::path_matches;
+use crate::response::{self, Response, StatusCode};
+
+/// Responds `503 Service Unavailable` to requests that take longer than the
+/// timeout.
+///
+/// The timeout can be overridden for the routes under a path, e.g. to give
+/// uploads more time. The override with the longest matching path wins.
+pub struct Timeout {
+    default: Duration,
+    routes: Vec<(String, Duration)>,
+}
+
+impl Timeout {
+    pub fn new(default: Duration) -> Self {
+        Self {
+            default,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Overrides the timeout for `path` and the paths under it.
+    pub fn route<S: Into<String>>(mut self, path: S, timeout: Duration) -> Self {
+        let path = path.into();
+        let path = path.trim_end_matches('/').to_owned();
+        self.routes.push((path, timeout));
+        self.routes.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        self
+    }
+
+    /// The timeout of a request to `path`.
+    pub fn timeout_for(&self, path: &str) -> Duration {
+        self.routes
+            .iter()
+            .find(|(prefix, _)| path_matches(prefix, path))
+            .map(|&(_, timeout)| timeout)
+            .unwrap_or(self.default)
+    }
+}
+
+impl<Data: Send + Sync + 'static> Middleware<Data> for Timeout {
+    fn handle<'a>(&'a self, cx: Context<Data>, next: Next<'a, Data>) -> BoxFuture<'a, Response> {
+        let timeout = self.timeout_for(cx.uri().path());
+        box_async! {
+            match await!(future::select(next.run(cx), Delay::new(timeout))) {
+                Either::Left((res, _)) => res,
+                Either::Right(_) => {
+                    log::warn!("Request timed out after {:?}", timeout);
+                    response::json(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        json!({ "message": "Request Timeout" }),
+                    )
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    async fn slow(_cx: Context<()>) -> Response {
+        let _ = await!(Delay::new(Duration::from_millis(200)));
+        response::empty(StatusCode::OK)
+    }
+
+    fn app() -> tide::App<()> {
+        let mut app = tide::App::new(());
+        app.middleware(
+            Timeout::new(Duration::from_millis(50)).route("/uploads", Duration::from_secs(5)),
+        );
+        app.at("/reads/slow").get(slow);
+        app.at("/uploads/slow").get(slow);
+        app
+    }
+
+    #[test]
+    fn test_timeout_for() {
+        let timeout = Timeout::new(Duration::from_secs(1))
+            .route("/api", Duration::from_secs(2))
+            .route("/api/uploads/", Duration::from_secs(3));
+
+        assert_eq!(timeout.timeout_for("/"), Duration::from_secs(1));
+        assert_eq!(timeout.timeout_for("/apis"), Duration::from_secs(1));
+        assert_eq!(timeout.timeout_for("/api"), Duration::from_secs(2));
+        assert_eq!(timeout.timeout_for("/api/users"), Duration::from_secs(2));
+        assert_eq!(
+            timeout.timeout_for("/api/uploads/1"),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn test_timeout_default() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/reads/slow").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 503);
+    }
+
+    #[test]
+    fn test_timeout_route_override() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/uploads/slow").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+    }
+}
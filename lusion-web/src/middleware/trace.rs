@@ -0,0 +1,76 @@
+//! Request tracing middleware.
+use futures::future::BoxFuture;
+use tide::middleware::{Middleware, Next};
+use tide::Context;
+use tracing::{field, info_span, Instrument};
+
+use crate::response::Response;
+
+/// Wraps every request in a `request` span with its method, path and
+/// response status.
+///
+/// Use it with a `lusion_db::traced::TracedPool` to nest the database spans
+/// in the request span.
+#[derive(Debug, Default)]
+pub struct TraceMiddleware;
+
+impl TraceMiddleware {
+    pub fn new() -> Self {
+        TraceMiddleware
+    }
+}
+
+impl<Data: Send + Sync + 'static> Middleware<Data> for TraceMiddleware {
+    fn handle<'a>(&'a self, cx: Context<Data>, next: Next<'a, Data>) -> BoxFuture<'a, Response> {
+        let span = info_span!(
+            "request",
+            method = %cx.method(),
+            path = %cx.uri().path(),
+            status = field::Empty,
+        );
+        box_async! {
+            let res = await!(next.run(cx).instrument(span.clone()));
+            span.record("status", &res.status().as_u16());
+            res
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EndpointResult;
+    use crate::pool::PoolExt;
+    use crate::response::{self, StatusCode};
+    use crate::test_helpers::*;
+    use lusion_db::traced::TracedPool;
+
+    async fn ping(cx: Context<TracedPool<TestPool<PgPool>>>) -> EndpointResult {
+        cx.db(|_| Ok(()))?;
+
+        Ok(response::empty(StatusCode::OK))
+    }
+
+    #[test]
+    fn test_trace_middleware_spans() {
+        let mut app = tide::App::new(TracedPool::new(init_pool()));
+        app.middleware(TraceMiddleware::new());
+        app.at("/ping").get(ping);
+
+        let mut server = init_service(app);
+        let subscriber = RecordingSubscriber::default();
+        let res = tracing::subscriber::with_default(subscriber.clone(), || {
+            let req = http::Request::get("/ping").to_request();
+            call_service(&mut server, req)
+        });
+        assert_eq!(res.status(), 200);
+
+        let spans = subscriber.spans();
+        let request = spans.iter().find(|span| span.name == "request").unwrap();
+        let db = spans
+            .iter()
+            .find(|span| span.name == "db.transaction")
+            .unwrap();
+        assert_eq!(db.parent, Some(request.id));
+    }
+}
@@ -0,0 +1,159 @@
+//! Pagination.
+use http::header::HeaderValue;
+use http::Uri;
+use lusion_db::page::Page;
+
+use crate::error::{Result, ResultExt};
+use crate::response::Response;
+
+const MAX_LIMIT: i64 = 100;
+
+/// The `offset` and `limit` query parameters of a paginated request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageQuery {
+    pub offset: i64,
+    pub limit: i64,
+}
+
+#[derive(Deserialize)]
+struct RawPageQuery {
+    #[serde(default)]
+    offset: i64,
+    limit: Option<i64>,
+}
+
+impl PageQuery {
+    /// Parse the page query of a request uri.
+    ///
+    /// Yields `None` without a `limit`, for an unpaged request. The limit is
+    /// clamped to `1..=100` and a negative offset is treated as zero.
+    pub fn from_uri(uri: &Uri) -> Result<Option<Self>> {
+        let query: RawPageQuery =
+            serde_urlencoded::from_str(uri.query().unwrap_or("")).user_error("Bad Request")?;
+
+        Ok(query.limit.map(|limit| PageQuery {
+            offset: query.offset.max(0),
+            limit: limit.max(1).min(MAX_LIMIT),
+        }))
+    }
+
+    pub fn page(&self) -> Page {
+        Page::new(self.limit, self.offset)
+    }
+}
+
+/// The query of `uri` for the page at `offset`, keeping any other parameter
+/// as it is, e.g. `fields`.
+fn page_uri(uri: &Uri, offset: i64, limit: i64) -> String {
+    let mut params = uri
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or("");
+            !param.is_empty() && name != "offset" && name != "limit"
+        })
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    params.push(format!("offset={}", offset));
+    params.push(format!("limit={}", limit));
+
+    format!("{}?{}", uri.path(), params.join("&"))
+}
+
+/// Build a `Link` header value with the `next` and `prev` pages of `uri`.
+///
+/// Yields `None` when there is neither a next nor a previous page.
+pub fn link_header(uri: &Uri, offset: i64, limit: i64, total: i64) -> Option<String> {
+    let mut links = Vec::new();
+    if offset + limit < total {
+        links.push(format!(
+            "<{}>; rel=\"next\"",
+            page_uri(uri, offset + limit, limit)
+        ));
+    }
+    if offset > 0 {
+        links.push(format!(
+            "<{}>; rel=\"prev\"",
+            page_uri(uri, (offset - limit).max(0), limit)
+        ));
+    }
+
+    if links.is_empty() {
+        None
+    } else {
+        Some(links.join(", "))
+    }
+}
+
+/// Set the `X-Total-Count` and `Link` headers of a paginated response.
+pub fn page_headers(mut res: Response, uri: &Uri, query: PageQuery, total: i64) -> Response {
+    let headers = res.headers_mut();
+    headers.insert("X-Total-Count", HeaderValue::from(total));
+    if let Some(link) = link_header(uri, query.offset, query.limit, total) {
+        if let Ok(value) = HeaderValue::from_str(&link) {
+            headers.insert(http::header::LINK, value);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(uri: &str) -> Uri {
+        uri.parse().unwrap()
+    }
+
+    #[test]
+    fn test_link_header_first_page() {
+        assert_eq!(
+            link_header(&uri("/users?limit=2"), 0, 2, 5),
+            Some(r#"</users?offset=2&limit=2>; rel="next""#.to_owned())
+        );
+    }
+
+    #[test]
+    fn test_link_header_middle_page() {
+        assert_eq!(
+            link_header(&uri("/users?offset=2&limit=2"), 2, 2, 5),
+            Some(
+                r#"</users?offset=4&limit=2>; rel="next", </users?offset=0&limit=2>; rel="prev""#
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_link_header_last_page() {
+        assert_eq!(
+            link_header(&uri("/users?offset=4&limit=2"), 4, 2, 5),
+            Some(r#"</users?offset=2&limit=2>; rel="prev""#.to_owned())
+        );
+        assert_eq!(link_header(&uri("/users?limit=20"), 0, 20, 5), None);
+    }
+
+    #[test]
+    fn test_link_header_keeps_other_params() {
+        assert_eq!(
+            link_header(&uri("/users?limit=2&fields=id,username"), 0, 2, 5),
+            Some(r#"</users?fields=id,username&offset=2&limit=2>; rel="next""#.to_owned())
+        );
+    }
+
+    #[test]
+    fn test_page_query_from_uri() {
+        assert_eq!(
+            PageQuery::from_uri(&uri("/users?offset=-3&limit=1000")).unwrap(),
+            Some(PageQuery {
+                offset: 0,
+                limit: 100
+            })
+        );
+
+        assert_eq!(PageQuery::from_uri(&uri("/users")).unwrap(), None);
+        assert_eq!(PageQuery::from_uri(&uri("/users?offset=2")).unwrap(), None);
+        assert!(PageQuery::from_uri(&uri("/users?limit=abc")).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Database pool access.
+use lusion_db::prelude::*;
+use tide::Context;
+
+use crate::error::{Result, ResultExt};
+
+/// An extension to `Context` that provides the database pool.
+pub trait PoolExt {
+    type Pool: DbPool;
+
+    /// Get the database pool.
+    fn pool(&self) -> &Self::Pool;
+
+    /// Executes the given function inside of a database transaction.
+    ///
+    /// The `DbError` is mapped to the matching HTTP status, e.g. a missing
+    /// row to 404 and an unavailable pool to 503.
+    fn db<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&<Self::Pool as DbPool>::Connection) -> std::result::Result<T, DbError>;
+}
+
+impl<Pool: DbPool> PoolExt for Context<Pool> {
+    type Pool = Pool;
+
+    fn pool(&self) -> &Pool {
+        self.app_data()
+    }
+
+    fn db<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Pool::Connection) -> std::result::Result<T, DbError>,
+    {
+        self.app_data().transaction(f).db_error()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EndpointResult;
+    use crate::response::{self, StatusCode};
+    use crate::test_helpers::*;
+    use lusion_db::error::DieselError;
+
+    async fn failing(cx: Context<TestPool<PgPool>>) -> EndpointResult {
+        let kind: String = cx.param("kind").unwrap();
+        cx.db(|_| {
+            Err::<(), _>(match kind.as_str() {
+                "not_found" => DbError::Diesel(DieselError::NotFound),
+                "get_not_found" => DbError::NotFound,
+                "conflict" => DbError::Conflict("duplicate key".to_owned()),
+                "unavailable" => DbError::ShuttingDown,
+                _ => DbError::Diesel(DieselError::RollbackTransaction),
+            })
+        })?;
+
+        Ok(response::empty(StatusCode::OK))
+    }
+
+    #[test]
+    fn test_db_error_statuses() {
+        let mut app = tide::App::new(init_pool());
+        app.at("/failing/:kind").get(failing);
+
+        let mut server = init_service(app);
+        let cases = [
+            ("not_found", 404, "Not Found"),
+            ("get_not_found", 404, "Not Found"),
+            ("conflict", 409, "Conflict"),
+            ("unavailable", 503, "Service Unavailable"),
+            ("other", 500, "Internal Server Error"),
+        ];
+        for &(kind, status, message) in cases.iter() {
+            let req = http::Request::get(format!("/failing/{}", kind)).to_request();
+            let res = call_service(&mut server, req);
+            assert_eq!(res.status(), status);
+            assert_eq!(res.read_body(), format!(r#"{{"message":"{}"}}"#, message));
+        }
+    }
+}
@@ -1,2 +1,323 @@
 //! Http Request.
 pub use http_service::Request;
+
+use std::str::FromStr;
+
+use futures::StreamExt;
+use http::header::{self, HeaderMap};
+use serde::de::DeserializeOwned;
+use tide::Context;
+
+use crate::error::{Error, ErrorKind, JsonError, Result, ResultExt};
+use crate::middleware::limits::BodyLimit;
+use crate::route::RouteParams;
+
+/// An extension to `Context` that provides request helpers.
+pub trait ContextExt {
+    /// Extract and parse an optional route parameter by name.
+    ///
+    /// Unlike `Context::param`, yields `None` when the route doesn't have the
+    /// parameter or it fails to parse as `T`.
+    ///
+    /// The endpoint must be wrapped with `route::with_params`, which declares
+    /// the parameters of its route; undeclared ones are missing. Without it,
+    /// yields an internal server error, like `require_ext`.
+    fn param_opt<T: FromStr>(&self, key: &str) -> Result<Option<T>>;
+
+    /// Get a request-scoped extension by type.
+    fn get_ext<T: Send + Sync + 'static>(&self) -> Option<&T>;
+
+    /// Insert a request-scoped extension, returning the previous one of the
+    /// same type.
+    fn insert_ext<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T>;
+
+    /// Get a request-scoped extension that must have been inserted earlier,
+    /// e.g. by a middleware.
+    ///
+    /// Yields an internal server error if it's missing.
+    fn require_ext<T: Send + Sync + 'static>(&self) -> Result<&T>;
+}
+
+impl<AppData> ContextExt for Context<AppData> {
+    fn param_opt<T: FromStr>(&self, key: &str) -> Result<Option<T>> {
+        // `Context::param` panics when `key` isn't a parameter of the route.
+        let params = self.require_ext::<RouteParams>()?;
+        if params.contains(key) {
+            Ok(self.param::<T>(key).ok())
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_ext<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.extensions().get::<T>()
+    }
+
+    fn insert_ext<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.extensions_mut().insert(value)
+    }
+
+    fn require_ext<T: Send + Sync + 'static>(&self) -> Result<&T> {
+        self.get_ext::<T>()
+            .ok_or_else(|| ErrorKind::MissingExtension.into())
+    }
+}
+
+/// Reads the request body.
+///
+/// Unlike `Context::body_bytes`, stops reading with
+/// `ErrorKind::PayloadTooLarge` once more than the `max_body` of the `Limits`
+/// middleware has been read, whether or not the request has a
+/// `Content-Length`.
+pub async fn body_bytes<AppData>(cx: &mut Context<AppData>) -> Result<Vec<u8>> {
+    let limit = cx.get_ext::<BodyLimit>().map(|limit| limit.0);
+    let mut body = cx.take_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = await!(body.next()) {
+        let chunk = chunk.user_error("Bad Request")?;
+        if limit.map_or(false, |limit| (bytes.len() + chunk.len()) as u64 > limit) {
+            return Err(ErrorKind::PayloadTooLarge.into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Reads the request body as JSON.
+///
+/// Unlike `Context::body_json`, a body that fails to deserialize yields a
+/// `ErrorKind::InvalidJson` with the position and cause of the problem.
+pub async fn body_json_detailed<T, AppData>(cx: &mut Context<AppData>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let body = await!(body_bytes(cx))?;
+    serde_json::from_slice(&body)
+        .map_err(|err| Error::from(ErrorKind::InvalidJson(JsonError::from(&err))))
+}
+
+/// Like `body_json_detailed`, but first rejects a request whose
+/// `Content-Type` isn't `application/json` with
+/// `ErrorKind::UnsupportedMediaType`, for endpoints that only accept JSON.
+pub async fn body_json_strict<T, AppData>(cx: &mut Context<AppData>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    if !is_json(cx.headers()) {
+        return Err(ErrorKind::UnsupportedMediaType.into());
+    }
+    await!(body_json_detailed(cx))
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|hv| hv.to_str().ok())
+        .map(|content_type| {
+            let essence = content_type.split(';').next().unwrap_or("").trim();
+            essence.eq_ignore_ascii_case("application/json")
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EndpointResult;
+    use crate::response::{self, Response, StatusCode};
+    use crate::route::with_params;
+    use crate::test_helpers::*;
+
+    struct Marker(&'static str);
+
+    async fn present(mut cx: Context<()>) -> EndpointResult {
+        cx.insert_ext(Marker("present"));
+        let marker = cx.require_ext::<Marker>()?;
+        Ok(response::json(StatusCode::OK, marker.0))
+    }
+
+    async fn missing(cx: Context<()>) -> EndpointResult {
+        let marker = cx.require_ext::<Marker>()?;
+        Ok(response::json(StatusCode::OK, marker.0))
+    }
+
+    async fn optional(cx: Context<()>) -> Response {
+        let marker = cx.get_ext::<Marker>().map(|marker| marker.0);
+        response::json(StatusCode::OK, marker)
+    }
+
+    async fn user_id(cx: Context<()>) -> EndpointResult {
+        let user_id = cx.param_opt::<u32>("user_id")?;
+        Ok(response::json(StatusCode::OK, user_id))
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct Payload {
+        name: String,
+        age: u32,
+    }
+
+    async fn json_body(mut cx: Context<()>) -> EndpointResult {
+        let payload: Payload = await!(body_json_detailed(&mut cx))?;
+        Ok(response::json(StatusCode::OK, payload))
+    }
+
+    async fn strict_json_body(mut cx: Context<()>) -> EndpointResult {
+        let payload: Payload = await!(body_json_strict(&mut cx))?;
+        Ok(response::json(StatusCode::OK, payload))
+    }
+
+    fn app() -> tide::App<()> {
+        let mut app = tide::App::new(());
+        app.at("/users").get(with_params(&[], user_id));
+        app.at("/users/:user_id")
+            .get(with_params(&["user_id"], user_id));
+        app.at("/undeclared/:user_id")
+            .get(with_params(&[], user_id));
+        app.at("/unwrapped/:user_id").get(user_id);
+        app.at("/ext/present").get(present);
+        app.at("/ext/missing").get(missing);
+        app.at("/ext/optional").get(optional);
+        app.at("/json").post(json_body);
+        app.at("/json/strict").post(strict_json_body);
+        app
+    }
+
+    #[test]
+    fn test_param_opt_present() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/users/42").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "42");
+    }
+
+    #[test]
+    fn test_param_opt_missing() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/users").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "null");
+    }
+
+    #[test]
+    fn test_param_opt_undeclared() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/undeclared/42").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "null");
+    }
+
+    #[test]
+    fn test_param_opt_without_params_should_be_500() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/unwrapped/42").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 500);
+    }
+
+    #[test]
+    fn test_param_opt_malformed() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/users/abc").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "null");
+    }
+
+    #[test]
+    fn test_require_ext_present() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/ext/present").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "\"present\"");
+    }
+
+    #[test]
+    fn test_require_ext_missing_should_be_500() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/ext/missing").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 500);
+    }
+
+    #[test]
+    fn test_get_ext_missing() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/ext/optional").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), "null");
+    }
+
+    #[test]
+    fn test_body_json_detailed_ok() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/json").json(json!({ "name": "a", "age": 1 }));
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.read_body(), r#"{"name":"a","age":1}"#);
+    }
+
+    #[test]
+    fn test_body_json_detailed_missing_field() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/json").json(json!({ "name": "a" }));
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        let message = body["message"].as_str().unwrap();
+        assert!(message.contains("missing field `age`"));
+        assert_eq!(body["line"], 1);
+    }
+
+    #[test]
+    fn test_body_json_detailed_malformed() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/json")
+            .body(http_service::Body::from("{\"name\": \"a\",\n\"age\": }"))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 400);
+
+        let body: serde_json::Value = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(body["line"], 2);
+        assert_eq!(body["column"], 8);
+    }
+
+    #[test]
+    fn test_body_json_strict_ok() {
+        let mut server = init_service(app());
+        for content_type in &["application/json", "Application/JSON; charset=utf-8"] {
+            let req = http::Request::post("/json/strict")
+                .header(header::CONTENT_TYPE, *content_type)
+                .body(http_service::Body::from(r#"{"name":"a","age":1}"#))
+                .unwrap();
+            let res = call_service(&mut server, req);
+            assert_eq!(res.status(), 200);
+            assert_eq!(res.read_body(), r#"{"name":"a","age":1}"#);
+        }
+    }
+
+    #[test]
+    fn test_body_json_strict_should_be_415() {
+        let mut server = init_service(app());
+        let req = http::Request::post("/json/strict")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(http_service::Body::from(r#"{"name":"a","age":1}"#))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 415);
+        assert_eq!(res.read_body(), r#"{"message":"Unsupported Media Type"}"#);
+
+        let req = http::Request::post("/json/strict")
+            .body(http_service::Body::from(r#"{"name":"a","age":1}"#))
+            .unwrap();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 415);
+    }
+}
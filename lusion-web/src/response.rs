@@ -3,8 +3,11 @@ pub use http::status::StatusCode;
 pub use http_service::{Body, Response};
 pub use tide::response::IntoResponse;
 
+use std::fmt::{self, Display};
+
 use bytes::Bytes;
-use futures::Stream;
+use futures::{Stream, StreamExt};
+use http::header::{self, HeaderMap};
 use http::HttpTryFrom;
 
 /// Set a empty body and generate `Response`
@@ -30,6 +33,69 @@ where
         .unwrap()
 }
 
+/// An event of a server-sent events stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    event: Option<String>,
+    data: String,
+    id: Option<String>,
+}
+
+impl SseEvent {
+    pub fn new<S: Into<String>>(data: S) -> Self {
+        SseEvent {
+            event: None,
+            data: data.into(),
+            id: None,
+        }
+    }
+
+    /// Set the event type, `message` by default.
+    pub fn event<S: Into<String>>(mut self, event: S) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set the event id, which the client resends as `Last-Event-ID` when
+    /// it reconnects.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+}
+
+/// Formats the event in the `text/event-stream` wire format, splitting a
+/// multi-line data into one `data:` line per line.
+impl Display for SseEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref event) = self.event {
+            writeln!(f, "event: {}", event)?;
+        }
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {}", line.trim_end_matches('\r'))?;
+        }
+        if let Some(ref id) = self.id {
+            writeln!(f, "id: {}", id)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Set a `text/event-stream` body sending each event of `events` as its own
+/// chunk and generate `Response`
+pub fn sse<S>(events: S) -> Response
+where
+    S: Stream<Item = SseEvent> + Send + 'static,
+{
+    let body = events.map(|event| Ok::<_, std::io::Error>(Bytes::from(event.to_string())));
+    http::Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .body(Body::from_stream(body))
+        .unwrap()
+}
+
 /// Set a json body and generate `Response`
 pub fn json<S, T: serde::Serialize>(status: S, t: T) -> Response
 where
@@ -42,6 +108,47 @@ where
         .unwrap()
 }
 
+/// Set a json body tagged with `etag` and generate `Response`, or generate a
+/// `304 Not Modified` if the request's `If-None-Match` matches `etag`.
+pub fn json_etag<S, T: serde::Serialize>(
+    request_headers: &HeaderMap,
+    etag: &str,
+    status: S,
+    t: T,
+) -> Response
+where
+    StatusCode: HttpTryFrom<S>,
+{
+    let mut res = if etag_matches(request_headers, etag) {
+        empty(StatusCode::NOT_MODIFIED)
+    } else {
+        json(status, t)
+    };
+    if let Ok(value) = header::HeaderValue::from_str(etag) {
+        res.headers_mut().insert(header::ETAG, value);
+    }
+    res
+}
+
+/// Format a weak entity tag.
+pub fn weak_etag<T: std::fmt::Display>(tag: T) -> String {
+    format!("W/\"{}\"", tag)
+}
+
+/// Whether `If-None-Match` matches `etag`, using the weak comparison.
+fn etag_matches(request_headers: &HeaderMap, etag: &str) -> bool {
+    fn opaque(tag: &str) -> &str {
+        tag.trim().trim_start_matches("W/")
+    }
+
+    request_headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
 /// Set a html body and generate `Response`
 pub fn html<S, T: Into<Bytes> + Send>(status: S, t: T) -> Response
 where
@@ -77,6 +184,24 @@ mod tests {
         assert_eq!(body, "");
     }
 
+    #[test]
+    fn test_sse() {
+        let events = futures::stream::iter(vec![
+            SseEvent::new("hello"),
+            SseEvent::new("line 1\r\nline 2").event("update").id("2"),
+        ]);
+        let resp = sse(events);
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/event-stream");
+        assert_eq!(resp.headers()[header::CACHE_CONTROL], "no-cache");
+
+        let body = resp.read_body();
+        assert_eq!(
+            body,
+            "data: hello\n\nevent: update\ndata: line 1\ndata: line 2\nid: 2\n\n"
+        );
+    }
+
     #[test]
     fn test_json() {
         let resp = json(http::StatusCode::OK, json!({ "message": "test" }));
@@ -100,6 +225,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_json_etag() {
+        let etag = weak_etag(1);
+        let resp = json_etag(&HeaderMap::new(), &etag, StatusCode::OK, "test");
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::ETAG], "W/\"1\"");
+        assert_eq!(resp.read_body(), "\"test\"");
+    }
+
+    #[test]
+    fn test_json_etag_not_modified() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            header::HeaderValue::from_static("\"0\", W/\"1\""),
+        );
+
+        let resp = json_etag(&headers, &weak_etag(1), StatusCode::OK, "test");
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers()[header::ETAG], "W/\"1\"");
+        assert_eq!(resp.read_body(), "");
+
+        let resp = json_etag(&headers, &weak_etag(2), StatusCode::OK, "test");
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[test]
     fn test_html() {
         let resp = html(http::StatusCode::OK, "<h1>Hello World</h1>");
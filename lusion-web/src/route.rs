@@ -0,0 +1,146 @@
+//! Route helpers.
+pub use http::Method;
+
+use futures::future::{self, Ready};
+use http::header::{self, HeaderValue};
+use tide::{Context, Endpoint, Route};
+
+use crate::request::ContextExt;
+use crate::response::{self, Response, StatusCode};
+
+/// An extension to `Route` that rejects unsupported methods.
+pub trait RouteExt {
+    /// Respond `405 Method Not Allowed` with an `Allow` header to every
+    /// method other than `methods`.
+    ///
+    /// `HEAD` falls back to `GET` in the router, so it's only rejected when
+    /// `GET` isn't allowed.
+    fn allow(&mut self, methods: &[Method]) -> &mut Self;
+}
+
+impl<'a, AppData: Send + Sync + 'static> RouteExt for Route<'a, AppData> {
+    fn allow(&mut self, methods: &[Method]) -> &mut Self {
+        let allow = methods
+            .iter()
+            .map(Method::as_str)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let allow = HeaderValue::from_str(&allow).unwrap();
+
+        let rejected = [
+            Method::GET,
+            Method::HEAD,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ];
+        for method in rejected.iter() {
+            if methods.contains(method) {
+                continue;
+            }
+            if *method == Method::HEAD && methods.contains(&Method::GET) {
+                continue;
+            }
+            self.method(
+                method.clone(),
+                MethodNotAllowed {
+                    allow: allow.clone(),
+                },
+            );
+        }
+        self
+    }
+}
+
+struct MethodNotAllowed {
+    allow: HeaderValue,
+}
+
+impl<AppData> Endpoint<AppData> for MethodNotAllowed {
+    type Fut = Ready<Response>;
+
+    fn call(&self, _cx: Context<AppData>) -> Self::Fut {
+        let mut resp = response::empty(StatusCode::METHOD_NOT_ALLOWED);
+        resp.headers_mut().insert(header::ALLOW, self.allow.clone());
+        future::ready(resp)
+    }
+}
+
+/// The parameter names of the matched route, see `with_params`.
+#[derive(Debug, Clone)]
+pub(crate) struct RouteParams(&'static [&'static str]);
+
+impl RouteParams {
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.0.contains(&key)
+    }
+}
+
+/// Declares the parameters of the route `endpoint` is mounted on, e.g.
+/// `app.at("/users/:user_id").get(with_params(&["user_id"], get_user))`.
+///
+/// Tide doesn't expose the parameters of a route and `Context::param` panics
+/// for a missing one, so `ContextExt::param_opt` only yields the declared
+/// ones, and fails on an endpoint that isn't wrapped.
+pub fn with_params<E>(names: &'static [&'static str], endpoint: E) -> WithParams<E> {
+    WithParams {
+        params: RouteParams(names),
+        endpoint,
+    }
+}
+
+/// An endpoint declaring the parameters of its route, see `with_params`.
+pub struct WithParams<E> {
+    params: RouteParams,
+    endpoint: E,
+}
+
+impl<AppData, E: Endpoint<AppData>> Endpoint<AppData> for WithParams<E> {
+    type Fut = E::Fut;
+
+    fn call(&self, mut cx: Context<AppData>) -> Self::Fut {
+        cx.insert_ext(self.params.clone());
+        self.endpoint.call(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    async fn ok(_cx: Context<()>) -> Response {
+        response::empty(StatusCode::OK)
+    }
+
+    fn app() -> tide::App<()> {
+        let mut app = tide::App::new(());
+        app.at("/users").get(ok);
+        app.at("/users").post(ok);
+        app.at("/users").allow(&[Method::GET, Method::POST]);
+
+        app
+    }
+
+    #[test]
+    fn test_allowed_method_should_be_200() {
+        let mut server = init_service(app());
+        let req = http::Request::get("/users").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+    }
+
+    #[test]
+    fn test_disallowed_method_should_be_405() {
+        let mut server = init_service(app());
+        let req = http::Request::delete("/users").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 405);
+
+        let allow = res.headers().get(header::ALLOW);
+        assert_matches!(allow, Some(allow) => {
+            assert_eq!(allow, HeaderValue::from_static("GET, POST"));
+        });
+    }
+}
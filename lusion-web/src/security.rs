@@ -1,9 +1,11 @@
 //! Security context.
 use std::sync::{Arc, RwLock};
 
-use tide::error::StringError;
 use tide::Context;
 
+use crate::error::{internal_error, Result};
+use crate::request::ContextExt;
+
 const MIDDLEWARE_MISSING_MSG: &str = "SecurityMiddleware must be set";
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -55,41 +57,38 @@ struct SecurityContextInner {
 }
 
 /// An extension to `Context` that provides security context.
+///
+/// Each method yields an internal server error when `SecurityMiddleware`
+/// isn't installed.
 pub trait SecurityExt {
     /// Get current identity.
-    fn identity(&mut self) -> Result<Option<Identity>, StringError>;
+    fn identity(&mut self) -> Result<Option<Identity>>;
 
     /// Remember principal and authorities.
-    fn remember(&mut self, identity: Identity) -> Result<(), StringError>;
+    fn remember(&mut self, identity: Identity) -> Result<()>;
 
-    fn forget(&mut self) -> Result<(), StringError>;
+    fn forget(&mut self) -> Result<()>;
 }
 
 impl<AppData> SecurityExt for Context<AppData> {
-    fn identity(&mut self) -> Result<Option<Identity>, StringError> {
-        let sc = self
-            .extensions()
-            .get::<SecurityContext>()
-            .ok_or_else(|| StringError(MIDDLEWARE_MISSING_MSG.to_owned()))?;
+    fn identity(&mut self) -> Result<Option<Identity>> {
+        let sc = security_context(self)?;
 
         let locked_inner = sc
             .inner
             .read()
-            .map_err(|e| StringError(format!("Failed to get read lock: {}", e)))?;
+            .map_err(|e| internal_error(format!("Failed to get read lock: {}", e)))?;
 
         Ok(locked_inner.identity.clone())
     }
 
-    fn remember(&mut self, identity: Identity) -> Result<(), StringError> {
-        let sc = self
-            .extensions()
-            .get::<SecurityContext>()
-            .ok_or_else(|| StringError(MIDDLEWARE_MISSING_MSG.to_owned()))?;
+    fn remember(&mut self, identity: Identity) -> Result<()> {
+        let sc = security_context(self)?;
 
         let mut locked_inner = sc
             .inner
             .write()
-            .map_err(|e| StringError(format!("Failed to get write lock: {}", e)))?;
+            .map_err(|e| internal_error(format!("Failed to get write lock: {}", e)))?;
 
         locked_inner.identity = Some(identity);
         locked_inner.changed = true;
@@ -97,16 +96,13 @@ impl<AppData> SecurityExt for Context<AppData> {
         Ok(())
     }
 
-    fn forget(&mut self) -> Result<(), StringError> {
-        let sc = self
-            .extensions()
-            .get::<SecurityContext>()
-            .ok_or_else(|| StringError(MIDDLEWARE_MISSING_MSG.to_owned()))?;
+    fn forget(&mut self) -> Result<()> {
+        let sc = security_context(self)?;
 
         let mut locked_inner = sc
             .inner
             .write()
-            .map_err(|e| StringError(format!("Failed to get write lock: {}", e)))?;
+            .map_err(|e| internal_error(format!("Failed to get write lock: {}", e)))?;
 
         if locked_inner.identity.is_some() {
             locked_inner.identity = None;
@@ -116,3 +112,41 @@ impl<AppData> SecurityExt for Context<AppData> {
         Ok(())
     }
 }
+
+fn security_context<AppData>(cx: &Context<AppData>) -> Result<&SecurityContext> {
+    cx.require_ext::<SecurityContext>()
+        .map_err(|_| internal_error(MIDDLEWARE_MISSING_MSG))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{self, Response, StatusCode};
+    use crate::test_helpers::*;
+
+    async fn errors(mut cx: Context<()>) -> Response {
+        let errors = vec![
+            cx.identity().map(|_| ()),
+            cx.remember(Identity::new("user")),
+            cx.forget(),
+        ];
+        let messages = errors
+            .into_iter()
+            .map(|result| result.unwrap_err().to_string())
+            .collect::<Vec<_>>();
+        response::json(StatusCode::OK, messages)
+    }
+
+    #[test]
+    fn test_security_ext_without_middleware() {
+        let mut app = tide::App::new(());
+        app.at("/").get(errors);
+
+        let mut server = init_service(app);
+        let req = http::Request::get("/").to_request();
+        let res = call_service(&mut server, req);
+        assert_eq!(res.status(), 200);
+        let messages: Vec<String> = serde_json::from_str(&res.read_body()).unwrap();
+        assert_eq!(messages, vec![MIDDLEWARE_MISSING_MSG; 3]);
+    }
+}
@@ -1,6 +1,6 @@
 //! Test helpers.
 pub use lusion_db::pg::PgPool;
-pub use lusion_db::test::TestPool;
+pub use lusion_db::test::{SharedTestPool, TestPool};
 
 use cookie::Cookie;
 use futures::executor::block_on;
@@ -8,6 +8,10 @@ use http_service::{Body, Request, Response};
 use http_service_mock::{make_server, TestBackend};
 use tide::{App, Server};
 
+use crate::app::Config;
+use crate::middleware::fs::Static;
+use crate::middleware::security::SecurityMiddleware;
+
 pub fn init_pool() -> TestPool<PgPool> {
     let database_url = dotenv::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let pool = PgPool::new(&database_url).expect("Failed to create pool");
@@ -15,6 +19,38 @@ pub fn init_pool() -> TestPool<PgPool> {
     TestPool::with(pool)
 }
 
+/// A pool whose changes are seen by the later requests of a test, see
+/// `SharedTestPool`.
+pub fn init_shared_pool() -> SharedTestPool {
+    let database_url = dotenv::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::new(&database_url).expect("Failed to create pool");
+
+    SharedTestPool::with(pool).expect("Failed to check out a connection")
+}
+
+/// The default config with a fixed, all-zero auth signing key.
+pub fn init_config() -> Config {
+    Config {
+        auth_signing_key: vec![0; 32],
+        cookie_secure: false,
+        ..Config::default()
+    }
+}
+
+/// An app with the default `SecurityMiddleware` installed.
+pub fn init_app_with_security<AppData: Send + Sync + 'static>(data: AppData) -> App<AppData> {
+    let mut app = App::new(data);
+    app.middleware(SecurityMiddleware::default());
+    app
+}
+
+/// An app serving `./tests/resources` under `/static`.
+pub fn init_app_with_static<AppData: Send + Sync + 'static>(data: AppData) -> App<AppData> {
+    let mut app = App::new(data);
+    app.middleware(Static::new("/static", "./tests/resources"));
+    app
+}
+
 pub fn init_service<AppData: Send + Sync + 'static>(
     app: App<AppData>,
 ) -> TestBackend<Server<AppData>> {
@@ -44,6 +80,7 @@ impl RequestBuilderExt for http::request::Builder {
     }
 
     fn json<T: serde::Serialize>(&mut self, payload: T) -> Request {
+        self.header(http::header::CONTENT_TYPE, "application/json");
         self.body(Body::from(serde_json::to_string(&payload).unwrap()))
             .unwrap()
     }
@@ -57,6 +94,8 @@ pub trait ResponseExt {
     fn get_cookie(&self, name: &str) -> Option<Cookie<'static>>;
 
     fn read_body(self) -> String;
+
+    fn read_bytes(self) -> Vec<u8>;
 }
 
 impl ResponseExt for http::Response<Body> {
@@ -74,7 +113,78 @@ impl ResponseExt for http::Response<Body> {
     }
 
     fn read_body(self) -> String {
-        let bytes = block_on(self.into_body().into_vec()).unwrap();
-        String::from_utf8(bytes).unwrap()
+        String::from_utf8(self.read_bytes()).unwrap()
+    }
+
+    fn read_bytes(self) -> Vec<u8> {
+        block_on(self.into_body().into_vec()).unwrap()
+    }
+}
+
+#[cfg(feature = "trace")]
+pub use self::recording::RecordingSubscriber;
+
+#[cfg(feature = "trace")]
+mod recording {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::{span, Event, Id, Metadata, Subscriber};
+
+    /// A recorded `tracing` span.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RecordedSpan {
+        pub id: u64,
+        pub name: &'static str,
+        pub parent: Option<u64>,
+    }
+
+    /// A `tracing` subscriber that records the spans created on the current
+    /// thread.
+    #[derive(Clone, Default)]
+    pub struct RecordingSubscriber {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+        stack: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl RecordingSubscriber {
+        pub fn spans(&self) -> Vec<RecordedSpan> {
+            self.spans.lock().unwrap().clone()
+        }
+    }
+
+    impl Subscriber for RecordingSubscriber {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &span::Attributes) -> Id {
+            let parent = if attrs.is_contextual() {
+                self.stack.lock().unwrap().last().cloned()
+            } else {
+                attrs.parent().map(Id::into_u64)
+            };
+            let mut spans = self.spans.lock().unwrap();
+            let id = spans.len() as u64 + 1;
+            spans.push(RecordedSpan {
+                id,
+                name: attrs.metadata().name(),
+                parent,
+            });
+            Id::from_u64(id)
+        }
+
+        fn record(&self, _: &Id, _: &span::Record) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event) {}
+
+        fn enter(&self, span: &Id) {
+            self.stack.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, _: &Id) {
+            self.stack.lock().unwrap().pop();
+        }
     }
 }
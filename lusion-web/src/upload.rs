@@ -0,0 +1,161 @@
+//! File upload.
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::marker::Unpin;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use bytes::Bytes;
+use futures::channel::{mpsc, oneshot};
+use futures::executor;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+/// The number of chunks buffered for the writer thread.
+const WRITE_BUFFER: usize = 8;
+
+/// Streams `body` into the file at `dest` without buffering it in memory,
+/// returning the number of bytes written.
+///
+/// The body is written to a uniquely named temporary file next to `dest`
+/// which is renamed into place once complete. If the body exceeds `max_size`
+/// bytes or any error occurs, the temporary file is removed and `dest` is
+/// left untouched. The file is written on its own thread, so the blocking
+/// file system calls don't stall the executor.
+pub async fn save_stream<S, P>(mut body: S, dest: P, max_size: u64) -> io::Result<u64>
+where
+    S: Stream<Item = io::Result<Bytes>> + Unpin,
+    P: AsRef<Path>,
+{
+    let dest = dest.as_ref().to_owned();
+    let tmp = temp_path(&dest)?;
+    let (mut chunks, rx) = mpsc::channel(WRITE_BUFFER);
+    let (done, written) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = done.send(write_file(executor::block_on_stream(rx), &tmp, &dest));
+    });
+
+    let mut size = 0;
+    while let Some(chunk) = await!(body.next()) {
+        let chunk = chunk.and_then(|chunk| {
+            size += chunk.len() as u64;
+            if size > max_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("upload exceeds maximum size of {} bytes", max_size),
+                ));
+            }
+            Ok(chunk)
+        });
+
+        // The writer stops at the first error, which closes the channel.
+        let failed = chunk.is_err();
+        if await!(chunks.send(chunk)).is_err() || failed {
+            break;
+        }
+    }
+    drop(chunks);
+
+    match await!(written) {
+        Ok(result) => result.map(|_| size),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "upload writer panicked",
+        )),
+    }
+}
+
+/// Writes `chunks` into `tmp` and renames it to `dest`, removing `tmp` on
+/// the first error.
+fn write_file<I>(chunks: I, tmp: &Path, dest: &Path) -> io::Result<()>
+where
+    I: Iterator<Item = io::Result<Bytes>>,
+{
+    let result = File::create(tmp)
+        .and_then(|mut file| {
+            for chunk in chunks {
+                file.write_all(&chunk?)?;
+            }
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(tmp, dest));
+
+    if result.is_err() {
+        let _ = fs::remove_file(tmp);
+    }
+    result
+}
+
+/// A temporary path in the directory of `dest`, unique to each upload so
+/// concurrent uploads to the same `dest` don't share it.
+fn temp_path(dest: &Path) -> io::Result<PathBuf> {
+    let file_name = dest
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing file name"))?;
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(format!(".{}.part", Uuid::new_v4().to_simple()));
+
+    Ok(dest.with_file_name(tmp_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::stream;
+
+    fn chunks(count: usize, size: usize) -> impl Stream<Item = io::Result<Bytes>> + Unpin {
+        stream::iter((0..count).map(move |_| Ok(Bytes::from(vec![b'a'; size]))))
+    }
+
+    fn dest_path() -> PathBuf {
+        std::env::temp_dir().join(format!("lusion-upload-{}.bin", uuid::Uuid::new_v4()))
+    }
+
+    /// The temporary files left next to `dest`.
+    fn temp_files(dest: &Path) -> Vec<PathBuf> {
+        let prefix = dest.file_name().unwrap().to_str().unwrap();
+        fs::read_dir(dest.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                let name = path.file_name().unwrap().to_str().unwrap_or("");
+                name.starts_with(prefix) && name.ends_with(".part")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_save_stream_should_write_file() {
+        let dest = dest_path();
+        let result = block_on(save_stream(chunks(16, 65_536), &dest, 2 * 1024 * 1024));
+
+        assert_matches!(result, Ok(size) => {
+            assert_eq!(size, 16 * 65_536);
+        });
+        assert_eq!(fs::metadata(&dest).unwrap().len(), 16 * 65_536);
+        assert!(temp_files(&dest).is_empty());
+
+        fs::remove_file(&dest).unwrap();
+    }
+
+    #[test]
+    fn test_save_stream_exceeding_max_size_should_fail() {
+        let dest = dest_path();
+        let result = block_on(save_stream(chunks(4, 1024), &dest, 3000));
+
+        assert_matches!(result, Err(ref e) if e.kind() == io::ErrorKind::InvalidData);
+        assert!(!dest.exists());
+        assert!(temp_files(&dest).is_empty());
+    }
+
+    #[test]
+    fn test_temp_path_should_be_unique() {
+        let dest = dest_path();
+        let tmp = temp_path(&dest).unwrap();
+
+        assert_eq!(tmp.parent(), dest.parent());
+        assert_ne!(tmp, temp_path(&dest).unwrap());
+    }
+}
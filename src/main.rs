@@ -1,11 +1,11 @@
 //! An experimental, Web API based on async/await IO implementation.
+use std::time::Duration;
 use std::{env, io};
 
+use lusion_db::password::Argon2Hasher;
 use lusion_db::pg::PgPool;
-use lusion_web::middleware::fs::Static;
-use lusion_web::middleware::security::{CookieIdentityPolicy, SecurityMiddleware};
-
-static AUTH_SIGNING_KEY: &[u8] = &[0; 32];
+use lusion_db::slow_log::SlowQueryPool;
+use lusion_web::app::{AppBuilder, Config};
 
 fn main() -> io::Result<()> {
     env::set_var("RUST_LOG", "debug,lusion_web=debug");
@@ -14,29 +14,32 @@ fn main() -> io::Result<()> {
     env_logger::init();
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = PgPool::new(&database_url).expect("Failed to create pool");
-
-    let mut app = tide::App::new(pool);
-    app.middleware(SecurityMiddleware::new(
-        CookieIdentityPolicy::new(AUTH_SIGNING_KEY)
-            .path("/")
-            .name("auth-cookie")
-            .domain("localhost")
-            .secure(false)
-            .max_age(3600),
-    ));
-    app.middleware(Static::new("/images", "./images"));
-
-    app.at("/api").nest(|api| {
-        use lusion_web::endpoints::*;
-
-        api.at("/users").get(users::get_users);
-        api.at("/users").post(users::post_user);
-        api.at("/users/:user_id").get(users::get_user);
-        api.at("/users/:user_id").delete(users::delete_user);
-        api.at("/users/:user_id/password")
-            .put(users::put_user_password);
-    });
+    let mut builder = PgPool::builder();
+    if env::var("PASSWORD_HASHER").unwrap_or_default() == "argon2" {
+        builder = builder.password_hasher(Argon2Hasher::default());
+    }
+    if let Ok(size) = env::var("DATABASE_POOL_SIZE") {
+        builder = builder.max_size(size.parse().expect("DATABASE_POOL_SIZE must be a number"));
+    }
+    let pool = builder.build(&database_url).expect("Failed to create pool");
+
+    let slow_query_ms = env::var("SLOW_QUERY_MS")
+        .map(|ms| ms.parse().expect("SLOW_QUERY_MS must be a number"))
+        .unwrap_or(500);
+    let app_pool = SlowQueryPool::new(pool, Duration::from_millis(slow_query_ms));
+
+    let auth_signing_key = env::var("AUTH_SIGNING_KEY").expect("AUTH_SIGNING_KEY must be set");
+    assert!(
+        auth_signing_key.len() >= 32,
+        "AUTH_SIGNING_KEY must be at least 32 bytes"
+    );
+    let config = Config {
+        auth_signing_key: auth_signing_key.into_bytes(),
+        cookie_secure: env::var("COOKIE_SECURE").map_or(true, |secure| secure != "false"),
+        ..Config::default()
+    };
+
+    let app = AppBuilder::new(config, app_pool).build();
 
     Ok(app.serve("127.0.0.1:8000")?)
 }